@@ -1,4 +1,16 @@
 
+use crate::learning::argmax;
+use crate::merge::FitnessCombine;
+use crate::strategy::Strategy;
+use rand::Rng;
+
+/// Longest memory an agent can hold: 4-bit pairs in `memory_bits`, one
+/// slot short of the full word so window masks never overflow
+pub const MAX_MEM_LENGTH: u8 = 7;
+
+/// Fitness every agent starts with
+pub const INITIAL_FITNESS: f32 = 0.001;
+
 /// Actions that agents can take
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
@@ -28,9 +40,10 @@ pub struct Agent {
     // Core state (16 bytes)
     pub fitness: f32,
     pub memory_bits: u32,    // Bit-packed memory (up to 16 moves, 2 bits each)
-    pub mem_length: u8,      // Current memory size (0-5)
+    pub mem_length: u8,      // Current memory size (0-MAX_MEM_LENGTH)
     pub last_action: u8,     // Last action taken
-    _padding1: [u8; 2],
+    pub strategy: Strategy,  // Learner or fixed strategy
+    _padding1: u8,
     
     // Relationships (16 bytes)
     pub parent_1: u32,       // First parent ID (u32::MAX if none)
@@ -39,61 +52,101 @@ pub struct Agent {
     pub generation: u32,     // Merge generation counter
     
     // Q-learning state (32 bytes)
-    pub q_values: [f32; 8],  // Current Q-values for C,D,M,S with current state
+    pub alpha: f32,          // Learning rate
+    pub gamma: f32,          // Discount factor
+    _padding2: [u8; 8],
+    pub policy_owner: u32,   // Q-table owner when policies are per-agent
     pub policy_hash: u64,    // Hash of current memory state
     pub epsilon: f32,        // Exploration rate
     pub id: u32,
 }
 
 impl Agent {
-    pub fn new(id: u32) -> Self {
+    pub fn new<R: Rng>(id: u32, rng: &mut R) -> Self {
+        Self {
+            fitness: INITIAL_FITNESS,
+            mem_length: rng.gen_range(1..=5),
+            ..Self::vacant(id)
+        }
+    }
+    
+    /// Placeholder kept in the slot of a vacant cell: no fitness, no
+    /// history, no place in an organism and only its own policy owner
+    pub fn vacant(id: u32) -> Self {
         Self {
             id,
-            fitness: 0.001,
+            fitness: 0.0,
             memory_bits: 0,
-            mem_length: (rand::random::<u8>() % 5) + 1, // 1-5
+            mem_length: 1,
             last_action: 0,
-            _padding1: [0; 2],
+            strategy: Strategy::Learner,
+            _padding1: 0,
             
             parent_1: u32::MAX,
             parent_2: u32::MAX,
             child: u32::MAX,
             generation: 0,
             
-            q_values: [0.1; 8],
+            alpha: 0.2,
+            gamma: 0.95,
+            _padding2: [0; 8],
+            policy_owner: id,
             policy_hash: 0,
             epsilon: 0.1,
         }
     }
     
+    /// (my action, opponent action) from `age` games ago, 0 being the latest
+    #[inline]
+    pub fn remembered(&self, age: u32) -> (Action, Action) {
+        let pair = self.memory_bits >> ((self.mem_length as u32 - 1 - age) * 4);
+        (Action::from_u8((pair >> 2) as u8), Action::from_u8(pair as u8))
+    }
+    
     /// Add an action to memory (2 bits per action)
     pub fn add_to_memory(&mut self, my_action: Action, opp_action: Action) {
         if self.mem_length == 0 {
             return;
         }
         
-        // Shift memory left by 4 bits (2 actions × 2 bits)
-        self.memory_bits <<= 4;
+        // Shift memory right by 4 bits (2 actions × 2 bits), dropping the oldest pair
+        self.memory_bits >>= 4;
         
-        // Add new actions
-        self.memory_bits |= (my_action as u32) << 2;
-        self.memory_bits |= opp_action as u32;
+        // Add new actions in the most significant slot of the window
+        let pair = ((my_action as u32) << 2) | opp_action as u32;
+        self.memory_bits |= pair << ((self.mem_length as u32 - 1) * 4);
         
         // Mask to keep only relevant bits
         let mask = (1u32 << (self.mem_length as u32 * 4)) - 1;
         self.memory_bits &= mask;
     }
     
+    /// Replace memory with another history, keeping its newest pairs that fit in `mem_length`
+    pub fn set_memory(&mut self, memory_bits: u32, mem_length: u8) {
+        self.memory_bits = if self.mem_length <= mem_length {
+            memory_bits >> ((mem_length - self.mem_length) as u32 * 4)
+        } else {
+            memory_bits << ((self.mem_length - mem_length) as u32 * 4)
+        };
+    }
+    
+    /// Change the memory length, keeping the newest pairs that still fit
+    pub fn resize_memory(&mut self, mem_length: u8) {
+        let (bits, old_length) = (self.memory_bits, self.mem_length);
+        self.mem_length = mem_length;
+        self.set_memory(bits, old_length);
+    }
+    
     /// Get memory state as a hash for policy lookup
     pub fn get_memory_hash(&self, opp_memory: u32, opp_mem_length: u8) -> u64 {
         // Combine both agents' memories into a single hash
         let my_bits = self.memory_bits & ((1u32 << (self.mem_length as u32 * 4)) - 1);
         let opp_bits = opp_memory & ((1u32 << (opp_mem_length as u32 * 4)) - 1);
         
-        // Pack into u64: [my_mem_length|opp_mem_length|my_bits|opp_bits]
-        ((self.mem_length as u64) << 56) |
-        ((opp_mem_length as u64) << 48) |
-        ((my_bits as u64) << 32) |
+        // Pack into u64: [my_mem_length:4|opp_mem_length:4|my_bits:28|opp_bits:28]
+        ((self.mem_length as u64) << 60) |
+        ((opp_mem_length as u64) << 56) |
+        ((my_bits as u64) << 28) |
         (opp_bits as u64)
     }
     
@@ -102,65 +155,80 @@ impl Agent {
         self.child != u32::MAX || (self.parent_1 != u32::MAX && self.parent_2 != u32::MAX)
     }
     
-    /// Get the size of the organism this agent belongs to
-    pub fn get_organism_size(&self) -> u32 {
-        if !self.is_multicellular() {
-            1
-        } else {
-            // This will be computed by traversing the parent/child tree
-            // For now, return generation as a proxy
-            self.generation + 1
+    /// Newborn single-cell copy of this agent for cell `id`: same strategy,
+    /// policy owner, memory length and hyperparameters, but no history,
+    /// no fitness and no place in an organism. Per-agent policies are
+    /// copied to a fresh owner by `Grid::evolve`.
+    pub fn offspring(&self, id: u32) -> Self {
+        Self {
+            id,
+            fitness: INITIAL_FITNESS,
+            memory_bits: 0,
+            last_action: 0,
+            parent_1: u32::MAX,
+            parent_2: u32::MAX,
+            child: u32::MAX,
+            generation: 0,
+            policy_hash: 0,
+            ..self.clone()
         }
     }
+    
+    /// Detach a dissolved super-agent from the merge tree
+    pub fn retire(&mut self) {
+        self.parent_1 = u32::MAX;
+        self.parent_2 = u32::MAX;
+        self.child = u32::MAX;
+        self.fitness = 0.0;
+    }
 }
 
 /// Compact policy representation for memory efficiency
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct CompactPolicy {
-    pub q_values: [f32; 4], // Q-values for C, D, M, S
+    pub q_values: [f32; 4],   // Q-values for C, D, M, S
+    pub q_values_b: [f32; 4], // Second estimator, only used by Double Q-learning
+    pub visits: [u32; 4],     // Updates per action, for UCB exploration
 }
 
 impl CompactPolicy {
-    pub fn new() -> Self {
-        Self {
-            q_values: [
-                rand::random::<f32>() * 0.1,
-                rand::random::<f32>() * 0.1,
-                rand::random::<f32>() * 0.1,
-                rand::random::<f32>() * 0.1,
-            ],
-        }
+    pub fn new<R: Rng>(rng: &mut R) -> Self {
+        let q_values = [
+            rng.gen::<f32>() * 0.1,
+            rng.gen::<f32>() * 0.1,
+            rng.gen::<f32>() * 0.1,
+            rng.gen::<f32>() * 0.1,
+        ];
+        Self { q_values, q_values_b: q_values, visits: [0; 4] }
     }
     
     /// Get action using epsilon-greedy strategy
-    pub fn get_action(&self, epsilon: f32) -> Action {
-        if rand::random::<f32>() < epsilon {
+    pub fn get_action<R: Rng>(&self, epsilon: f32, rng: &mut R) -> Action {
+        if rng.gen::<f32>() < epsilon {
             // Random action
-            Action::from_u8(rand::random::<u8>() & 0b11)
+            Action::from_u8(rng.gen::<u8>() & 0b11)
         } else {
-            // Greedy action
-            let max_idx = self.q_values
-                .iter()
-                .enumerate()
-                .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
-                .map(|(idx, _)| idx)
-                .unwrap();
-            Action::from_u8(max_idx as u8)
+            self.greedy_action()
         }
     }
     
+    /// Action with the highest Q-value, ties going to the first action
+    pub fn greedy_action(&self) -> Action {
+        Action::from_u8(argmax(&self.q_values) as u8)
+    }
+    
     /// Calculate an updated Q-value array using TD learning
     pub fn calculate_updated_q_values(
         &self,
         action: Action,
         reward: f32,
-        next_max_q: f32,
+        next_q: f32,
         alpha: f32,
         gamma: f32,
     ) -> [f32; 4] {
         let mut new_q = self.q_values;
         let idx = action as usize;
-        let td_target = reward + gamma * next_max_q;
+        let td_target = reward + gamma * next_q;
         let td_error = td_target - new_q[idx];
         new_q[idx] += alpha * td_error;
         new_q
@@ -173,8 +241,10 @@ pub enum DeferredOp {
     Merge {
         agent1: u32,
         agent2: u32,
-        new_fitness: f32,
-        inherit_from: u32,
+        fitness: FitnessCombine,
+        inherit_from: u32, // Source of memory and everything but the policy
+        policy_from: u32,  // Source of the Q-table
+        fitter: u32,       // Parent that was fitter before the game
     },
     Split {
         agent: u32,
@@ -193,9 +263,15 @@ mod tests {
         assert_eq!(std::mem::align_of::<Agent>(), 64);
     }
     
+    #[test]
+    fn test_greedy_action_ties_go_first() {
+        let policy = CompactPolicy { q_values: [1.0, 2.0, 2.0, 0.0], q_values_b: [0.0; 4], visits: [0; 4] };
+        assert_eq!(policy.greedy_action(), Action::Defect);
+    }
+    
     #[test]
     fn test_memory_packing() {
-        let mut agent = Agent::new(0);
+        let mut agent = Agent::new(0, &mut rand::thread_rng());
         agent.mem_length = 3;
         
         agent.add_to_memory(Action::Cooperate, Action::Defect);
@@ -206,4 +282,18 @@ mod tests {
         // In bits: 01|00|10|11|00|01
         assert_eq!(agent.memory_bits & 0xFFF, 0b010010110001);
     }
+    
+    #[test]
+    fn test_set_memory_keeps_newest() {
+        let mut agent = Agent::new(0, &mut rand::thread_rng());
+        agent.mem_length = 2;
+        
+        // DC|SM|CD from a 3-slot history: only DC|SM fits
+        agent.set_memory(0b010010110001, 3);
+        assert_eq!(agent.memory_bits, 0b01001011);
+        
+        agent.mem_length = 4;
+        agent.set_memory(0b010010110001, 3);
+        assert_eq!(agent.memory_bits, 0b0100101100010000);
+    }
 }
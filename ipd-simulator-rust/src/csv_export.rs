@@ -1,4 +1,6 @@
-use crate::grid::Statistics;
+use crate::agent::{Action, MAX_MEM_LENGTH};
+use crate::grid::{JointActionCounts, Statistics};
+use crate::strategy::Strategy;
 use csv::Writer;
 use std::path::{Path, PathBuf};
 use std::error::Error;
@@ -50,7 +52,7 @@ impl BufferedCsvExporter {
         
         // Write header if file is new
         if !file_exists {
-            writer.write_record(&[
+            let mut header: Vec<String> = [
                 "timestep",
                 "total_agents",
                 "avg_fitness",
@@ -65,12 +67,42 @@ impl BufferedCsvExporter {
                 "multicellular_fitness",
                 "unicellular_cooperators",
                 "multicellular_cooperators",
-            ])?;
+                "organisms",
+                "mean_organism_size",
+                "max_organism_size",
+                "agent_slots",
+                "live_agents",
+                "peak_live_agents",
+                "agent_memory_bytes",
+                "policy_states",
+                "policy_inserts",
+                "policy_evictions",
+                "policy_memory_bytes",
+                "migrations",
+                "replacements",
+                "stress",
+                "stress_delta",
+            ]
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+            header.extend(JointActionCounts::PAIRS.iter().map(|&(a, b)| {
+                format!("games_{}{}", action_letter(a), action_letter(b))
+            }));
+            for param in ["alpha", "gamma", "epsilon"] {
+                header.extend(["mean", "std", "min", "max"].map(|moment| format!("{}_{}", param, moment)));
+            }
+            header.push("mem_length_mean".to_string());
+            header.extend((1..=MAX_MEM_LENGTH).map(|length| format!("mem_length_{}", length)));
+            for strategy in Strategy::ALL {
+                header.extend(["cells", "avg_fitness", "cooperation_rate"].map(|field| format!("{}_{}", strategy.name(), field)));
+            }
+            writer.write_record(&header)?;
         }
         
         // Write all buffered records
         for record in &self.buffer {
-            writer.write_record(&[
+            let mut row = vec![
                 record.timestep.to_string(),
                 record.stats.total_agents.to_string(),
                 record.stats.avg_fitness().to_string(),
@@ -85,7 +117,42 @@ impl BufferedCsvExporter {
                 record.stats.multicellular_fitness.to_string(),
                 record.stats.unicellular_cooperation.to_string(),
                 record.stats.multicellular_cooperation.to_string(),
-            ])?;
+                record.stats.organisms.to_string(),
+                record.stats.mean_organism_size().to_string(),
+                record.stats.max_organism_size.to_string(),
+                record.stats.pass_stats.agent_slots.to_string(),
+                record.stats.pass_stats.live_agents.to_string(),
+                record.stats.pass_stats.peak_live_agents.to_string(),
+                record.stats.pass_stats.agent_memory_bytes.to_string(),
+                record.stats.pass_stats.policy_states.to_string(),
+                record.stats.pass_stats.policy_inserts.to_string(),
+                record.stats.pass_stats.policy_evictions.to_string(),
+                record.stats.pass_stats.policy_memory_bytes.to_string(),
+                record.stats.pass_stats.migrations.to_string(),
+                record.stats.pass_stats.replacements.to_string(),
+                record.stats.stress.to_string(),
+                record.stats.pass_stats.joint_actions.stress().to_string(),
+            ];
+            let games = &record.stats.pass_stats.joint_actions;
+            row.extend(JointActionCounts::PAIRS.iter().map(|&(a, b)| games.get(a, b).to_string()));
+            for summary in [&record.stats.alpha, &record.stats.gamma, &record.stats.epsilon] {
+                row.extend([
+                    summary.mean.to_string(),
+                    summary.std.to_string(),
+                    summary.min.to_string(),
+                    summary.max.to_string(),
+                ]);
+            }
+            row.push(record.stats.mean_mem_length().to_string());
+            row.extend(record.stats.mem_lengths[1..].iter().map(|count| count.to_string()));
+            for by_strategy in &record.stats.by_strategy {
+                row.extend([
+                    by_strategy.cells.to_string(),
+                    by_strategy.avg_fitness().to_string(),
+                    by_strategy.cooperation_rate().to_string(),
+                ]);
+            }
+            writer.write_record(&row)?;
         }
         
         writer.flush()?;
@@ -99,3 +166,13 @@ impl BufferedCsvExporter {
         Ok(())
     }
 }
+
+/// Lowercase action initial, as in the JS `payofftable` keys
+fn action_letter(action: Action) -> char {
+    match action {
+        Action::Cooperate => 'c',
+        Action::Defect => 'd',
+        Action::Merge => 'm',
+        Action::Split => 's',
+    }
+}
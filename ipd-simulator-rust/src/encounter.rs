@@ -0,0 +1,96 @@
+use rand::Rng;
+use std::str::FromStr;
+
+/// Most rounds a geometric encounter can last, so `w` near 1 stays bounded
+pub const MAX_ROUNDS: u32 = 10_000;
+
+/// How many rounds two agents play each time they are paired.
+///
+/// Parsed from `N` for a fixed count or `geometric:W`, where another round
+/// follows each one with continuation probability `W`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rounds {
+    Fixed(u32),
+    Geometric(f64),
+}
+
+impl Rounds {
+    /// Rounds for one encounter; a fixed count draws nothing
+    pub fn draw<R: Rng>(self, rng: &mut R) -> u32 {
+        match self {
+            Rounds::Fixed(rounds) => rounds,
+            Rounds::Geometric(w) => {
+                let mut rounds = 1;
+                while rounds < MAX_ROUNDS && rng.gen::<f64>() < w {
+                    rounds += 1;
+                }
+                rounds
+            }
+        }
+    }
+}
+
+impl FromStr for Rounds {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        match spec.split(':').collect::<Vec<_>>().as_slice() {
+            [count] => match count.trim().parse() {
+                Ok(0) => Err("an encounter needs at least one round".to_string()),
+                Ok(rounds) => Ok(Rounds::Fixed(rounds)),
+                Err(_) => Err(format!("invalid round count \"{}\"", count)),
+            },
+            ["geometric", w] => {
+                let w: f64 = w.trim().parse().map_err(|_| format!("invalid continuation probability \"{}\"", w))?;
+                if !(0.0..1.0).contains(&w) {
+                    return Err(format!("continuation probability {} is outside [0, 1)", w));
+                }
+                Ok(Rounds::Geometric(w))
+            }
+            _ => Err(format!("expected N or geometric:W, got \"{}\"", spec)),
+        }
+    }
+}
+
+/// What an encounter adds to each player's fitness
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum RoundPayoff {
+    /// The sum of the round payoffs
+    Accumulate,
+    /// The mean payoff per round played
+    Average,
+}
+
+/// Repeated play within one pairing
+#[derive(Debug, Clone, Copy)]
+pub struct Encounter {
+    pub rounds: Rounds,
+    pub payoff: RoundPayoff,
+}
+
+impl Default for Encounter {
+    /// One round, as in the JS `fight()`
+    fn default() -> Self {
+        Self { rounds: Rounds::Fixed(1), payoff: RoundPayoff::Accumulate }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_round_counts() {
+        let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(0);
+        assert_eq!("4".parse::<Rounds>().unwrap().draw(&mut rng), 4);
+        assert!("0".parse::<Rounds>().is_err());
+        assert!("geometric:1".parse::<Rounds>().is_err());
+
+        // Geometric encounters last 1 / (1 - w) rounds on average
+        let rounds: Rounds = "geometric:0.9".parse().unwrap();
+        let mean = (0..20_000).map(|_| rounds.draw(&mut rng) as f64).sum::<f64>() / 20_000.0;
+        assert!((mean - 10.0).abs() < 0.5, "{}", mean);
+        assert_eq!("geometric:0".parse::<Rounds>().unwrap().draw(&mut rng), 1);
+    }
+}
@@ -0,0 +1,279 @@
+use crate::grid::{Grid, PolicyMode};
+use crate::rng::{self, SimRng, DOMAIN_EVOLUTION};
+use rand::Rng;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+/// How agents are replaced between generations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum EvolutionRule {
+    /// A random single-cell agent dies; a neighbor chosen by fitness fills its cell
+    DeathBirth,
+    /// An organism chosen by fitness from the whole grid places offspring on a
+    /// random neighboring cell, which may be vacant
+    BirthDeath,
+    /// A random single-cell agent adopts a random neighbor's traits with
+    /// probability `1 / (1 + exp(-selection * (f_neighbor - f_self)))`
+    Fermi,
+}
+
+/// Selection between learning lifetimes
+#[derive(Debug, Clone, Copy)]
+pub struct Evolution {
+    pub rule: EvolutionRule,
+    /// Selection strength: 0 is neutral drift, larger values favor fitter agents more
+    pub selection: f32,
+    /// Timesteps between generations
+    pub period: u64,
+}
+
+impl Evolution {
+    /// Relative chance of reproducing, `exp(selection * (fitness - best))`
+    fn weight(&self, fitness: f32, best: f32) -> f64 {
+        ((self.selection * (fitness - best)) as f64).exp()
+    }
+
+    /// Index into `fitness` drawn with probability proportional to its weight
+    fn pick(&self, fitness: &[f32], rng: &mut SimRng) -> usize {
+        let best = fitness.iter().copied().fold(f32::MIN, f32::max);
+        let total: f64 = fitness.iter().map(|&f| self.weight(f, best)).sum();
+        let mut draw = rng.gen::<f64>() * total;
+        for (i, &f) in fitness.iter().enumerate() {
+            draw -= self.weight(f, best);
+            if draw < 0.0 {
+                return i;
+            }
+        }
+        fitness.len() - 1
+    }
+}
+
+impl Grid {
+    /// Run one generation of `evolution`, returning how many agents were replaced.
+    ///
+    /// A generation is one event per live unit: per single-cell agent for
+    /// death-birth and Fermi, per organism for birth-death. Events run one
+    /// after another from a single random stream, each seeing the grid as
+    /// earlier events left it, except that birth-death draws parents with
+    /// the fitness the generation started with. Organisms can reproduce
+    /// into neighboring cells but never die; dying agents are single cells.
+    /// Newborns start over as `Agent::offspring`; Fermi imitators keep their
+    /// fitness and history and take on only the model's traits. With
+    /// per-agent policies both get a copy of the model's table rather than
+    /// sharing it, and tables no live agent uses any more are dropped.
+    pub(crate) fn evolve(&mut self, evolution: Evolution) -> usize {
+        let mut rng = rng::stream(self.seed, self.timestep, DOMAIN_EVOLUTION, 0);
+        let mut neighbors = Vec::with_capacity(8);
+        let mut replaced = 0;
+        let mut copies = FxHashMap::default();
+        let mut replaced_owners = Vec::new();
+
+        // Single-cell agents, which are the only ones that can die
+        let sites: Vec<usize> = self.active_mask.iter_ones().filter(|&cell| self.root_cache[cell] as usize == cell).collect();
+
+        match evolution.rule {
+            EvolutionRule::DeathBirth => {
+                for _ in 0..sites.len() {
+                    let site = sites[rng.gen_range(0..sites.len())];
+                    let parents = self.neighboring_roots(site, &mut neighbors);
+                    if parents.is_empty() {
+                        continue;
+                    }
+                    let fitness: Vec<f32> = parents.iter().map(|&p| self.agents[p].fitness).collect();
+                    let parent = parents[evolution.pick(&fitness, &mut rng)];
+                    replaced_owners.push(self.agents[site].policy_owner);
+                    self.agents[site] = self.agents[parent].offspring(site as u32);
+                    self.own_policy_copy(site, &mut copies);
+                    replaced += 1;
+                }
+            }
+            EvolutionRule::BirthDeath => {
+                let roots = self.active_roots();
+                let fitness: Vec<f32> = roots.iter().map(|&root| self.agents[root].fitness).collect();
+                let best = fitness.iter().copied().fold(f32::MIN, f32::max);
+                let cumulative: Vec<f64> = fitness
+                    .iter()
+                    .scan(0.0, |total, &f| {
+                        *total += evolution.weight(f, best);
+                        Some(*total)
+                    })
+                    .collect();
+                let total = cumulative.last().copied().unwrap_or(0.0);
+                for _ in 0..roots.len() {
+                    let draw = rng.gen::<f64>() * total;
+                    let parent = roots[cumulative.partition_point(|&c| c <= draw).min(roots.len() - 1)];
+
+                    // Offspring land next to a random cell of the parent, on a
+                    // single-cell agent or a vacant cell
+                    let cells = self.member_cells(parent);
+                    let cell = cells[rng.gen_range(0..cells.len())];
+                    self.get_neighbors(cell, &mut neighbors);
+                    if neighbors.is_empty() {
+                        continue;
+                    }
+                    let target = neighbors[rng.gen_range(0..neighbors.len())];
+                    if self.root_cache[target] as usize != target {
+                        continue;
+                    }
+                    replaced_owners.push(self.agents[target].policy_owner);
+                    self.agents[target] = self.agents[parent].offspring(target as u32);
+                    self.own_policy_copy(target, &mut copies);
+                    self.active_mask.set(target, true);
+                    replaced += 1;
+                }
+            }
+            EvolutionRule::Fermi => {
+                for _ in 0..sites.len() {
+                    let site = sites[rng.gen_range(0..sites.len())];
+                    let models = self.neighboring_roots(site, &mut neighbors);
+                    if models.is_empty() {
+                        continue;
+                    }
+                    let model = self.agents[models[rng.gen_range(0..models.len())]].clone();
+                    let gain = model.fitness - self.agents[site].fitness;
+                    if rng.gen::<f32>() >= 1.0 / (1.0 + (-evolution.selection * gain).exp()) {
+                        continue;
+                    }
+                    let agent = &mut self.agents[site];
+                    replaced_owners.push(agent.policy_owner);
+                    agent.strategy = model.strategy;
+                    agent.policy_owner = model.policy_owner;
+                    agent.alpha = model.alpha;
+                    agent.gamma = model.gamma;
+                    agent.epsilon = model.epsilon;
+                    agent.resize_memory(model.mem_length);
+                    self.own_policy_copy(site, &mut copies);
+                    replaced += 1;
+                }
+            }
+        }
+        if !copies.is_empty() {
+            let dropped = self.unused_owners(replaced_owners);
+            self.policy_table.copy_owners(&copies, &dropped);
+        }
+        replaced
+    }
+
+    /// The owners among `candidates` whose policy no live agent uses
+    fn unused_owners(&self, candidates: Vec<u32>) -> FxHashSet<u32> {
+        let mut unused: FxHashSet<u32> = candidates.into_iter().collect();
+        let free: FxHashSet<u32> = self.free_agents.iter().copied().collect();
+        for (idx, agent) in self.agents.iter().enumerate() {
+            if self.is_occupied(idx) && !free.contains(&(idx as u32)) {
+                unused.remove(&agent.policy_owner);
+            }
+        }
+        unused
+    }
+
+    /// With per-agent policies, move agent `idx` from the table it inherited
+    /// to a copy under a fresh owner id, recorded in `copies` as `new -> source`.
+    ///
+    /// Nothing learns during a generation, so a copy of a copy is taken
+    /// straight from the original and all copies are made in one pass at the end.
+    fn own_policy_copy(&mut self, idx: usize, copies: &mut FxHashMap<u32, u32>) {
+        if self.policy_mode != PolicyMode::PerAgent {
+            return;
+        }
+        let inherited = self.agents[idx].policy_owner;
+        let source = copies.get(&inherited).copied().unwrap_or(inherited);
+        let owner = self.fresh_policy_owner();
+        copies.insert(owner, source);
+        self.agents[idx].policy_owner = owner;
+    }
+
+    /// Occupied organisms bordering cell `cell`, excluding its own, in order of first appearance
+    fn neighboring_roots(&self, cell: usize, neighbors: &mut Vec<usize>) -> Vec<usize> {
+        self.get_neighbors(cell, neighbors);
+        let own = self.root_cache[cell] as usize;
+        let mut roots = Vec::new();
+        for &n in neighbors.iter() {
+            let root = self.root_cache[n] as usize;
+            if root != own && self.is_occupied(root) && !roots.contains(&root) {
+                roots.push(root);
+            }
+        }
+        roots
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::INITIAL_FITNESS;
+    use crate::strategy::Strategy;
+
+    #[test]
+    fn test_fit_strategy_spreads() {
+        for rule in [EvolutionRule::DeathBirth, EvolutionRule::BirthDeath, EvolutionRule::Fermi] {
+            // A row of fit defectors across the middle of the grid
+            let mut grid = Grid::with_seed(7, 7, 3);
+            for (cell, agent) in grid.agents.iter_mut().enumerate() {
+                agent.fitness = 1.0;
+                if cell / 7 == 3 {
+                    agent.strategy = Strategy::AllD;
+                    agent.fitness = 10.0;
+                }
+            }
+
+            let evolution = Evolution { rule, selection: 5.0, period: 1 };
+            let replaced = grid.evolve(evolution);
+            let converts: Vec<usize> = (0..49)
+                .filter(|&cell| cell / 7 != 3 && grid.agents[cell].strategy == Strategy::AllD)
+                .collect();
+            assert!(replaced > 0 && !converts.is_empty(), "{:?}", rule);
+
+            // Newborns start over; imitators keep what they earned
+            let expected = if rule == EvolutionRule::Fermi { 1.0 } else { INITIAL_FITNESS };
+            for cell in converts {
+                assert_eq!(grid.agents[cell].id as usize, cell);
+                assert_eq!(grid.agents[cell].fitness, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_offspring_copy_per_agent_policies() {
+        let mut grid = Grid::with_seed(5, 5, 6);
+        grid.policy_mode = PolicyMode::PerAgent;
+        for (cell, agent) in grid.agents.iter_mut().enumerate() {
+            agent.fitness = if cell == 12 { 10.0 } else { 1.0 };
+        }
+        let parent_key = grid.policy_key(&grid.agents[12], 3);
+        let parent_policy = grid.policy_table.get_or_create(parent_key);
+
+        let evolution = Evolution { rule: EvolutionRule::DeathBirth, selection: 5.0, period: 1 };
+        assert!(grid.evolve(evolution) > 0);
+        let children: Vec<usize> = (0..25).filter(|&cell| grid.agents[cell].policy_owner >= 25).collect();
+        assert!(!children.is_empty());
+
+        // Every newborn has its own table, starting as a copy of the one it came from
+        let mut owners: Vec<u32> = children.iter().map(|&cell| grid.agents[cell].policy_owner).collect();
+        owners.sort_unstable();
+        owners.dedup();
+        assert_eq!(owners.len(), children.len());
+        let copies = children
+            .iter()
+            .filter(|&&cell| grid.policy_table.get_or_create(grid.policy_key(&grid.agents[cell], 3)) == parent_policy)
+            .count();
+        assert!(copies > 0);
+    }
+
+    #[test]
+    fn test_replaced_policies_are_dropped() {
+        let mut grid = Grid::with_seed(5, 5, 6);
+        grid.policy_mode = PolicyMode::PerAgent;
+        for rule in [EvolutionRule::DeathBirth, EvolutionRule::BirthDeath, EvolutionRule::Fermi] {
+            let evolution = Evolution { rule, selection: 1.0, period: 1 };
+            for _ in 0..20 {
+                for agent in &grid.agents {
+                    grid.policy_table.get_or_create(grid.policy_key(agent, 3));
+                }
+                grid.evolve(evolution);
+                grid.timestep += 1;
+
+                // One state per owner, and only for owners someone still uses
+                assert!(grid.policy_table.len() <= 25, "{:?}", rule);
+            }
+        }
+    }
+}
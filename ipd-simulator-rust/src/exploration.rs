@@ -0,0 +1,206 @@
+use crate::agent::{Action, CompactPolicy};
+use crate::learning::argmax;
+use rand::Rng;
+
+/// How agents trade off exploiting and exploring their Q-values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExplorationStrategy {
+    /// Random action with probability ε, greedy otherwise (JS `payoff`)
+    EpsilonGreedy,
+    /// Softmax over Q-values with a temperature
+    Boltzmann,
+    /// Greedy on Q-values plus a bonus for rarely tried actions
+    Ucb,
+}
+
+/// How the exploration parameter changes over the run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ScheduleKind {
+    /// Keep the starting value
+    Constant,
+    /// Move linearly to the end value over the horizon, then hold it
+    Linear,
+    /// Decay towards the end value with the horizon as time constant
+    Exponential,
+    /// Multiply by the step factor once per horizon, stopping at the end value
+    Step,
+}
+
+/// Annealing schedule for ε, temperature or the UCB constant
+#[derive(Debug, Clone, Copy)]
+pub struct Schedule {
+    pub kind: ScheduleKind,
+    pub end: f32,
+    pub horizon: u64,
+    pub step_factor: f32,
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Self { kind: ScheduleKind::Constant, end: 0.0, horizon: 1000, step_factor: 0.5 }
+    }
+}
+
+impl Schedule {
+    /// The parameter at `timestep`, starting from `start` at timestep 0
+    pub fn value(&self, start: f32, timestep: u64) -> f32 {
+        let horizon = self.horizon.max(1);
+        match self.kind {
+            ScheduleKind::Constant => start,
+            ScheduleKind::Linear => {
+                let progress = (timestep as f32 / horizon as f32).min(1.0);
+                start + (self.end - start) * progress
+            }
+            ScheduleKind::Exponential => {
+                self.end + (start - self.end) * (-(timestep as f32) / horizon as f32).exp()
+            }
+            ScheduleKind::Step => {
+                let steps = (timestep / horizon).min(i32::MAX as u64) as i32;
+                let value = start * self.step_factor.powi(steps);
+                value.clamp(start.min(self.end), start.max(self.end))
+            }
+        }
+    }
+}
+
+/// Exploration configuration for a run
+#[derive(Debug, Clone, Copy)]
+pub struct Exploration {
+    pub strategy: ExplorationStrategy,
+    pub schedule: Schedule,
+    pub temperature: f32,
+    pub ucb_c: f32,
+}
+
+impl Default for Exploration {
+    fn default() -> Self {
+        Self {
+            strategy: ExplorationStrategy::EpsilonGreedy,
+            schedule: Schedule::default(),
+            temperature: 1.0,
+            ucb_c: 1.0,
+        }
+    }
+}
+
+impl Exploration {
+    /// Action selection in effect at `timestep`, with `epsilon` as the
+    /// starting ε of ε-greedy runs
+    pub fn selector(&self, epsilon: f32, timestep: u64) -> ActionSelector {
+        match self.strategy {
+            ExplorationStrategy::EpsilonGreedy => ActionSelector::EpsilonGreedy(self.schedule.value(epsilon, timestep)),
+            ExplorationStrategy::Boltzmann => ActionSelector::Boltzmann(self.schedule.value(self.temperature, timestep)),
+            ExplorationStrategy::Ucb => ActionSelector::Ucb(self.schedule.value(self.ucb_c, timestep)),
+        }
+    }
+}
+
+/// An exploration strategy with its parameter fixed for one timestep
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ActionSelector {
+    EpsilonGreedy(f32),
+    Boltzmann(f32),
+    Ucb(f32),
+}
+
+impl ActionSelector {
+    pub fn choose<R: Rng>(self, policy: &CompactPolicy, rng: &mut R) -> Action {
+        match self {
+            ActionSelector::EpsilonGreedy(epsilon) => policy.get_action(epsilon, rng),
+            ActionSelector::Boltzmann(_) => {
+                let weights = self.probabilities(policy);
+                let mut draw = rng.gen::<f32>();
+                for (idx, &p) in weights.iter().enumerate() {
+                    if draw < p {
+                        return Action::from_u8(idx as u8);
+                    }
+                    draw -= p;
+                }
+                Action::Split
+            }
+            ActionSelector::Ucb(c) => Action::from_u8(ucb_action(policy, c) as u8),
+        }
+    }
+
+    /// Probability of each action in a state with this policy
+    pub fn probabilities(self, policy: &CompactPolicy) -> [f32; 4] {
+        match self {
+            ActionSelector::EpsilonGreedy(epsilon) => {
+                let mut probs = [epsilon / 4.0; 4];
+                probs[argmax(&policy.q_values)] += 1.0 - epsilon;
+                probs
+            }
+            ActionSelector::Boltzmann(temperature) if temperature > f32::EPSILON => {
+                let max_q = policy.q_values.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+                let weights = policy.q_values.map(|q| ((q - max_q) / temperature).exp());
+                let total: f32 = weights.iter().sum();
+                weights.map(|w| w / total)
+            }
+            ActionSelector::Boltzmann(_) => {
+                // Zero temperature is plain greedy
+                let mut probs = [0.0; 4];
+                probs[argmax(&policy.q_values)] = 1.0;
+                probs
+            }
+            ActionSelector::Ucb(c) => {
+                let mut probs = [0.0; 4];
+                probs[ucb_action(policy, c)] = 1.0;
+                probs
+            }
+        }
+    }
+}
+
+/// UCB1: untried actions first, then Q plus `c * sqrt(ln N / n)`
+fn ucb_action(policy: &CompactPolicy, c: f32) -> usize {
+    if let Some(untried) = policy.visits.iter().position(|&n| n == 0) {
+        return untried;
+    }
+    let total = policy.visits.iter().map(|&n| n as f32).sum::<f32>();
+    let scores: [f32; 4] = std::array::from_fn(|a| {
+        policy.q_values[a] + c * (total.ln() / policy.visits[a] as f32).sqrt()
+    });
+    argmax(&scores)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_schedules() {
+        let mut schedule = Schedule { kind: ScheduleKind::Linear, end: 0.0, horizon: 100, step_factor: 0.5 };
+        assert_eq!(schedule.value(1.0, 0), 1.0);
+        assert_eq!(schedule.value(1.0, 50), 0.5);
+        assert_eq!(schedule.value(1.0, 500), 0.0);
+
+        schedule.kind = ScheduleKind::Exponential;
+        assert!((schedule.value(1.0, 100) - (-1.0f32).exp()).abs() < 1e-6);
+
+        schedule.kind = ScheduleKind::Step;
+        schedule.end = 0.2;
+        assert_eq!(schedule.value(1.0, 99), 1.0);
+        assert_eq!(schedule.value(1.0, 100), 0.5);
+        assert_eq!(schedule.value(1.0, 1000), 0.2);
+    }
+
+    #[test]
+    fn test_selectors() {
+        let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(0);
+        let mut policy = CompactPolicy::new(&mut rng);
+        policy.q_values = [0.0, 1.0, 0.0, 0.0];
+
+        let probs = ActionSelector::Boltzmann(1.0).probabilities(&policy);
+        assert!((probs.iter().sum::<f32>() - 1.0).abs() < 1e-6);
+        assert!(probs[1] > probs[0]);
+        assert_eq!(ActionSelector::Boltzmann(0.0).choose(&policy, &mut rng), Action::Defect);
+
+        // UCB tries every action once before trusting Q-values
+        policy.visits = [3, 0, 1, 1];
+        assert_eq!(ActionSelector::Ucb(1.0).choose(&policy, &mut rng), Action::Defect);
+        policy.visits = [1, 100, 1, 1];
+        assert_ne!(ActionSelector::Ucb(1.0).choose(&policy, &mut rng), Action::Defect);
+        assert_eq!(ActionSelector::Ucb(0.0).choose(&policy, &mut rng), Action::Defect);
+    }
+}
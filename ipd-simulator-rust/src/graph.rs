@@ -0,0 +1,254 @@
+use crate::rng::{self, DOMAIN_GRAPH};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::error::Error;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Undirected interaction graph in compressed adjacency form
+#[derive(Debug, Clone)]
+pub struct Graph {
+    offsets: Vec<usize>,
+    targets: Vec<u32>,
+}
+
+impl Graph {
+    /// Build from undirected edges; self-loops and repeated edges are dropped
+    pub fn from_edges(node_count: usize, edges: &[(u32, u32)]) -> Self {
+        let mut lists = vec![Vec::new(); node_count];
+        for &(a, b) in edges {
+            if a != b {
+                lists[a as usize].push(b);
+                lists[b as usize].push(a);
+            }
+        }
+        let mut offsets = Vec::with_capacity(node_count + 1);
+        let mut targets = Vec::new();
+        offsets.push(0);
+        for mut list in lists {
+            list.sort_unstable();
+            list.dedup();
+            targets.extend(list);
+            offsets.push(targets.len());
+        }
+        Self { offsets, targets }
+    }
+
+    /// Load an edge list: one `u v` pair of 0-based node ids per line, `#` starting a comment
+    pub fn from_file(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("cannot read edge list {}: {}", path.display(), e))?;
+        let mut edges = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let ids: Vec<u32> = line
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|part| !part.is_empty())
+                .map(|part| part.parse())
+                .collect::<Result<_, _>>()
+                .map_err(|_| format!("{}:{}: expected two node ids, got \"{}\"", path.display(), number + 1, line))?;
+            match ids.as_slice() {
+                [a, b] => edges.push((*a, *b)),
+                _ => return Err(format!("{}:{}: expected two node ids, got \"{}\"", path.display(), number + 1, line).into()),
+            }
+        }
+        let node_count = edges.iter().map(|&(a, b)| a.max(b) as usize + 1).max().unwrap_or(0);
+        if node_count == 0 {
+            return Err(format!("edge list {} has no edges", path.display()).into());
+        }
+        Ok(Self::from_edges(node_count, &edges))
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.targets.len() / 2
+    }
+
+    #[inline]
+    pub fn neighbors(&self, node: usize) -> &[u32] {
+        &self.targets[self.offsets[node]..self.offsets[node + 1]]
+    }
+}
+
+/// Random graph generator, parsed from `watts-strogatz:N:K:P`,
+/// `barabasi-albert:N:M` or `random-regular:N:D`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GraphSpec {
+    /// Ring of `n` nodes joined to their `k` nearest, each edge rewired with probability `p`
+    WattsStrogatz { n: usize, k: usize, p: f64 },
+    /// Preferential attachment: each new node links to `m` nodes chosen by degree
+    BarabasiAlbert { n: usize, m: usize },
+    /// Every node has exactly `d` neighbors
+    RandomRegular { n: usize, d: usize },
+}
+
+impl GraphSpec {
+    pub fn node_count(&self) -> usize {
+        match *self {
+            GraphSpec::WattsStrogatz { n, .. } | GraphSpec::BarabasiAlbert { n, .. } | GraphSpec::RandomRegular { n, .. } => n,
+        }
+    }
+
+    /// Generate the graph; the same seed always gives the same graph
+    pub fn build(&self, seed: u64) -> Result<Graph, String> {
+        let mut rng = rng::stream(seed, 0, DOMAIN_GRAPH, 0);
+        match *self {
+            GraphSpec::WattsStrogatz { n, k, p } => {
+                if k % 2 == 1 || k >= n {
+                    return Err(format!("Watts-Strogatz needs an even k below n, got n={} k={}", n, k));
+                }
+                let mut adjacent = vec![std::collections::BTreeSet::new(); n];
+                for node in 0..n {
+                    for step in 1..=k / 2 {
+                        let other = (node + step) % n;
+                        adjacent[node].insert(other);
+                        adjacent[other].insert(node);
+                    }
+                }
+                // Rewire each ring edge's far end, keeping the graph simple
+                for step in 1..=k / 2 {
+                    for node in 0..n {
+                        let other = (node + step) % n;
+                        if !rng.gen_bool(p) || !adjacent[node].contains(&other) || adjacent[node].len() >= n - 1 {
+                            continue;
+                        }
+                        let target = loop {
+                            let candidate = rng.gen_range(0..n);
+                            if candidate != node && !adjacent[node].contains(&candidate) {
+                                break candidate;
+                            }
+                        };
+                        adjacent[node].remove(&other);
+                        adjacent[other].remove(&node);
+                        adjacent[node].insert(target);
+                        adjacent[target].insert(node);
+                    }
+                }
+                let edges: Vec<_> = adjacent
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(a, set)| set.iter().filter(move |&&b| a < b).map(move |&b| (a as u32, b as u32)))
+                    .collect();
+                Ok(Graph::from_edges(n, &edges))
+            }
+            GraphSpec::BarabasiAlbert { n, m } => {
+                if m == 0 || m >= n {
+                    return Err(format!("Barabasi-Albert needs 0 < m < n, got n={} m={}", n, m));
+                }
+                // Seed with a clique of m + 1 nodes; `ends` lists each node once per edge end
+                let mut edges = Vec::new();
+                let mut ends = Vec::new();
+                for a in 0..=m as u32 {
+                    for b in 0..a {
+                        edges.push((b, a));
+                        ends.extend([a, b]);
+                    }
+                }
+                let mut chosen = Vec::with_capacity(m);
+                for node in m as u32 + 1..n as u32 {
+                    chosen.clear();
+                    while chosen.len() < m {
+                        let target = ends[rng.gen_range(0..ends.len())];
+                        if !chosen.contains(&target) {
+                            chosen.push(target);
+                        }
+                    }
+                    for &target in &chosen {
+                        edges.push((target, node));
+                        ends.extend([target, node]);
+                    }
+                }
+                Ok(Graph::from_edges(n, &edges))
+            }
+            GraphSpec::RandomRegular { n, d } => {
+                if d >= n || (n * d) % 2 == 1 {
+                    return Err(format!("a {}-regular graph on {} nodes does not exist", d, n));
+                }
+                // Pair up edge stubs at random, restarting on self-loops or repeated edges
+                for _ in 0..1000 {
+                    let mut stubs: Vec<u32> = (0..n as u32).flat_map(|node| std::iter::repeat_n(node, d)).collect();
+                    stubs.shuffle(&mut rng);
+                    let edges: Vec<_> = stubs.chunks_exact(2).map(|pair| (pair[0].min(pair[1]), pair[0].max(pair[1]))).collect();
+                    let mut sorted = edges.clone();
+                    sorted.sort_unstable();
+                    sorted.dedup();
+                    if sorted.len() == edges.len() && edges.iter().all(|&(a, b)| a != b) {
+                        return Ok(Graph::from_edges(n, &edges));
+                    }
+                }
+                Err(format!("could not sample a {}-regular graph on {} nodes; try a smaller degree", d, n))
+            }
+        }
+    }
+}
+
+impl FromStr for GraphSpec {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = spec.split(':').collect();
+        let count = |text: &str| -> Result<usize, String> {
+            text.trim().parse().map_err(|_| format!("invalid count \"{}\" in \"{}\"", text, spec))
+        };
+        match parts.as_slice() {
+            ["watts-strogatz", n, k, p] => {
+                let p: f64 = p.trim().parse().map_err(|_| format!("invalid probability \"{}\" in \"{}\"", p, spec))?;
+                if !(0.0..=1.0).contains(&p) {
+                    return Err(format!("rewiring probability {} is outside [0, 1]", p));
+                }
+                Ok(GraphSpec::WattsStrogatz { n: count(n)?, k: count(k)?, p })
+            }
+            ["barabasi-albert", n, m] => Ok(GraphSpec::BarabasiAlbert { n: count(n)?, m: count(m)? }),
+            ["random-regular", n, d] => Ok(GraphSpec::RandomRegular { n: count(n)?, d: count(d)? }),
+            _ => Err(format!(
+                "expected watts-strogatz:N:K:P, barabasi-albert:N:M or random-regular:N:D, got \"{}\"",
+                spec
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn degrees(graph: &Graph) -> Vec<usize> {
+        (0..graph.node_count()).map(|node| graph.neighbors(node).len()).collect()
+    }
+
+    #[test]
+    fn test_generators() {
+        let ring: GraphSpec = "watts-strogatz:20:4:0".parse().unwrap();
+        let graph = ring.build(1).unwrap();
+        assert!(degrees(&graph).iter().all(|&d| d == 4));
+        assert_eq!(graph.neighbors(0), &[1, 2, 18, 19]);
+
+        let rewired = "watts-strogatz:20:4:0.5".parse::<GraphSpec>().unwrap().build(1).unwrap();
+        assert_eq!(rewired.edge_count(), 40);
+
+        let scale_free = "barabasi-albert:50:2".parse::<GraphSpec>().unwrap().build(1).unwrap();
+        assert_eq!(scale_free.edge_count(), 3 + 2 * 47);
+        assert!(degrees(&scale_free).iter().all(|&d| d >= 2));
+
+        let regular = "random-regular:30:3".parse::<GraphSpec>().unwrap().build(1).unwrap();
+        assert!(degrees(&regular).iter().all(|&d| d == 3));
+        assert!("random-regular:5:3".parse::<GraphSpec>().unwrap().build(1).is_err());
+        assert!("erdos:10".parse::<GraphSpec>().is_err());
+    }
+
+    #[test]
+    fn test_edge_list_file() {
+        let path = std::env::temp_dir().join("ipd_edge_list_test.txt");
+        std::fs::write(&path, "# triangle plus a tail\n0 1\n1 2\n2 0\n2,3\n3 3\n").unwrap();
+        let graph = Graph::from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!((graph.node_count(), graph.edge_count()), (4, 4));
+        assert_eq!(graph.neighbors(2), &[0, 1, 3]);
+    }
+}
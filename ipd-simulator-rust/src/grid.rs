@@ -1,14 +1,24 @@
-use crate::agent::{Agent, Action, CompactPolicy, DeferredOp};
+use crate::agent::{Agent, Action, CompactPolicy, DeferredOp, MAX_MEM_LENGTH};
+use crate::encounter::{Encounter, RoundPayoff};
+use crate::evolution::Evolution;
+use crate::exploration::Exploration;
+use crate::hyperparams::{Hyperparameters, ParamSummary};
+use crate::memory::MemoryRule;
+use crate::learning::{rebase, LearningParams, LearningRule, UpdateAggregation};
+use crate::merge::{self, MergeRule};
+use crate::neighborhood::{InteractionScope, NeighborhoodIndex};
+use crate::payoff::PayoffTable;
+use crate::policy_table::PolicyTable;
+use crate::rng::{self, SimRng, DOMAIN_GAME, DOMAIN_HYPERPARAMS, DOMAIN_INIT, DOMAIN_MEMORY, DOMAIN_MUTATION, DOMAIN_PAIRING, DOMAIN_POLICY_UPDATE, DOMAIN_STRATEGY};
+use crate::state::StateEncoder;
+use crate::strategy::{Strategy, StrategyLayout, StrategyMix};
+use crate::topology::Topology;
 use bitvec::prelude::*;
-use crossbeam::queue::ArrayQueue;
 use rayon::prelude::*;
 use std::collections::HashMap;
-use std::sync::Arc;
-use cht::HashMap as ChtHashMap;
 use std::time::Instant;
 use std::cell::RefCell;
 use rand::Rng;
-use std::sync::atomic::{AtomicU32, Ordering};
 
 thread_local!(static NEIGHBOR_BUFFER: RefCell<Vec<usize>> = RefCell::new(Vec::with_capacity(8)));
 
@@ -19,83 +29,55 @@ struct Interaction {
     agent2_idx: u32,
 }
 
-/// State changes for a single agent after an interaction
+/// State changes for a single agent after one round of an interaction
 #[derive(Debug, Clone, Copy)]
 struct StateUpdate {
     agent_idx: u32,
     fitness_delta: f32,
     action: Action,
-    policy_hash: u64,
-    new_q_values: [f32; 4],
+    opp_action: Action,
+    learned: Option<PolicyUpdate>, // None for fixed strategies and frozen policies
 }
 
-/// Payoff table for IPD game
-pub struct PayoffTable {
-    table: [[f32; 4]; 4],
+/// What a learner took from one game, for writing back to the policy table
+#[derive(Debug, Clone, Copy)]
+struct PolicyUpdate {
+    key: PolicyKey,
+    /// Policy the game learned from
+    base: CompactPolicy,
+    /// Policy of the state the game led to
+    next: CompactPolicy,
+    params: LearningParams,
+    /// The policy after learning from this game alone
+    updated: CompactPolicy,
 }
 
-impl PayoffTable {
-    pub fn default() -> Self {
-        let mut table = [[0.0; 4]; 4];
-        // C, D, M, S
-        table[0][0] = 8.0;  // CC
-        table[0][1] = 0.0;  // CD
-        table[0][2] = 8.0;  // CM
-        table[0][3] = 0.0;  // CS
-        
-        table[1][0] = 10.0; // DC
-        table[1][1] = 5.0;  // DD
-        table[1][2] = 10.0; // DM
-        table[1][3] = 0.0;  // DS
-        
-        table[2][0] = 8.0;  // MC
-        table[2][1] = 0.0;  // MD
-        table[2][2] = 0.0;  // MM
-        table[2][3] = 0.0;  // MS
-        
-        table[3][0] = 0.0;  // SC
-        table[3][1] = 0.0;  // SD
-        table[3][2] = 0.0;  // SM
-        table[3][3] = 0.0;  // SS
-        
-        Self { table }
-    }
-    
-    pub fn get(&self, my_action: Action, opp_action: Action) -> f32 {
-        self.table[my_action as usize][opp_action as usize]
-    }
+/// Who owns the Q-values an agent reads and writes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum PolicyMode {
+    /// One Q-table shared by every agent on the grid
+    Shared,
+    /// Each agent (organism) has its own Q-table, inherited on merge and split
+    PerAgent,
 }
 
-/// Shared policy table with lock-free concurrent hash map
-pub struct PolicyTable {
-    policies: ChtHashMap<u64, CompactPolicy>,
+/// Fitness given to each resurrected parent when an organism splits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SplitFitness {
+    /// Each parent inherits the super-agent's full fitness (algorithm.md §5.2)
+    Inherit,
+    /// Each parent receives half, conserving total fitness
+    Halve,
 }
 
-impl PolicyTable {
-    pub fn new(_capacity: usize) -> Self {
-        Self {
-            policies: ChtHashMap::new(),
-        }
-    }
-    
-    pub fn get_or_create(&self, state_hash: u64) -> CompactPolicy {
-        loop {
-            if let Some(policy) = self.policies.get(&state_hash) {
-                return policy.clone();
-            }
-            
-            let new_policy = CompactPolicy::new();
-            self.policies.insert(state_hash, new_policy.clone());
-            
-            if let Some(policy) = self.policies.get(&state_hash) {
-                return policy.clone();
-            }
-        }
-    }
-    
-    pub fn update(&self, state_hash: u64, policy: CompactPolicy) {
-        self.policies.insert(state_hash, policy);
-    }
+/// Owner used for all lookups when the policy table is shared
+pub const SHARED_POLICY_OWNER: u32 = u32::MAX;
+
+/// Key into the policy table: table owner plus memory state hash
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PolicyKey {
+    pub owner: u32,
+    pub state: u64,
 }
 
 /// Main grid structure for the simulation
@@ -104,29 +86,72 @@ pub struct Grid {
     pub active_mask: BitVec,
     pub grid_width: usize,
     pub grid_height: usize,
+    pub topology: Topology,
     pub policy_table: PolicyTable,
+    pub policy_mode: PolicyMode,
+    // Next unused per-agent policy owner; cells own the ids below the cell count
+    next_policy_owner: u32,
+    /// Play from the policy table without learning
+    pub freeze_policies: bool,
+    pub state_encoder: StateEncoder,
+    pub merge_rule: MergeRule,
+    pub split_fitness: SplitFitness,
+    pub memory_rule: MemoryRule,
     pub payoff_table: PayoffTable,
-    pub deferred_ops: Arc<ArrayQueue<DeferredOp>>,
-    root_cache: Vec<u32>,
+    pub encounter: Encounter,
+    /// Merges and splits queued outside a step, committed with the next step's own
+    pub deferred_ops: Vec<DeferredOp>,
+    pub(crate) root_cache: Vec<u32>,
+    
+    // Number of grid cells covered by each agent, indexed by agent id.
+    // Fixed once an agent exists, so merges append and splits need nothing.
+    organism_sizes: Vec<u32>,
+    
+    // Slots of retired super-agents, reused by later merges
+    pub(crate) free_agents: Vec<u32>,
+    peak_live_agents: usize,
+    
+    // Opponent selection
+    pub interaction_scope: InteractionScope,
+    pub games_per_organism: usize,
+    pub(crate) neighborhoods: Option<NeighborhoodIndex>,
+    
+    /// Chance per timestep that a single-cell agent moves to a vacant neighboring cell
+    pub migration_rate: f32,
+    /// Selection between learning lifetimes, if any
+    pub evolution: Option<Evolution>,
+    
+    // Run seed and step counter for the counter-based random streams
+    pub seed: u64,
+    pub timestep: u64,
     
     // Q-learning parameters
-    pub alpha: f32,
-    pub gamma: f32,
-    pub epsilon: f32,
+    pub learning_rule: LearningRule,
+    pub update_aggregation: UpdateAggregation,
+    pub hyperparams: Hyperparameters, // Per-agent alpha/gamma/epsilon live on each agent
+    pub exploration: Exploration,
 
     // Pass statistics
     pub pass_stats: PassStatistics,
+    /// Running JS `stress` total
+    pub stress: i64,
 }
 
 impl Grid {
     pub fn new(width: usize, height: usize) -> Self {
+        Self::with_seed(width, height, rand::random())
+    }
+    
+    /// Create a grid whose whole run is reproducible from `seed`
+    pub fn with_seed(width: usize, height: usize, seed: u64) -> Self {
         let total_agents = width * height;
         let mut agents = Vec::with_capacity(total_agents);
         let active_mask = bitvec![1; total_agents];
         
         // Initialize agents
+        let mut init_rng = rng::stream(seed, 0, DOMAIN_INIT, 0);
         for i in 0..total_agents {
-            agents.push(Agent::new(i as u32));
+            agents.push(Agent::new(i as u32, &mut init_rng));
         }
         
         Self {
@@ -134,36 +159,79 @@ impl Grid {
             active_mask,
             grid_width: width,
             grid_height: height,
-            policy_table: PolicyTable::new(10_000_000), // 10M policies
+            topology: Topology::default(),
+            policy_table: PolicyTable::new(seed),
+            policy_mode: PolicyMode::Shared,
+            next_policy_owner: total_agents as u32,
+            freeze_policies: false,
+            state_encoder: StateEncoder::Packed,
+            merge_rule: MergeRule::default(),
+            split_fitness: SplitFitness::Inherit,
+            memory_rule: MemoryRule::default(),
             payoff_table: PayoffTable::default(),
-            deferred_ops: Arc::new(ArrayQueue::new(1_000_000)),
-            root_cache: vec![0; total_agents],
-            alpha: 0.2,
-            gamma: 0.95,
-            epsilon: 0.1,
+            encounter: Encounter::default(),
+            deferred_ops: Vec::new(),
+            root_cache: (0..total_agents as u32).collect(),
+            organism_sizes: vec![1; total_agents],
+            free_agents: Vec::new(),
+            peak_live_agents: total_agents,
+            interaction_scope: InteractionScope::Cell,
+            games_per_organism: 1,
+            neighborhoods: None,
+            migration_rate: 0.0,
+            evolution: None,
+            seed,
+            timestep: 0,
+            learning_rule: LearningRule::QLearning,
+            update_aggregation: UpdateAggregation::LastWrite,
+            hyperparams: Hyperparameters::default(),
+            exploration: Exploration::default(),
             pass_stats: PassStatistics::default(),
+            stress: 0,
         }
     }
     
-    /// Get neighbors for an agent (8-connected), writing into a pre-allocated buffer.
+    /// Get neighbors for an agent under the grid's topology, writing into a pre-allocated buffer.
     #[inline]
     pub fn get_neighbors(&self, idx: usize, neighbors: &mut Vec<usize>) {
-        neighbors.clear();
-        let x = idx % self.grid_width;
-        let y = idx / self.grid_width;
-
-        for dy in -1i32..=1 {
-            for dx in -1i32..=1 {
-                if dx == 0 && dy == 0 { continue; }
-                
-                let nx = x as i32 + dx;
-                let ny = y as i32 + dy;
-                
-                if nx >= 0 && nx < self.grid_width as i32 && 
-                   ny >= 0 && ny < self.grid_height as i32 {
-                    neighbors.push((ny as usize * self.grid_width) + nx as usize);
-                }
-            }
+        self.topology.neighbors(idx, self.grid_width, self.grid_height, neighbors);
+    }
+    
+    /// Policy table key for an agent in a given memory state
+    #[inline]
+    pub fn policy_key(&self, agent: &Agent, state: u64) -> PolicyKey {
+        let owner = match self.policy_mode {
+            PolicyMode::Shared => SHARED_POLICY_OWNER,
+            PolicyMode::PerAgent => agent.policy_owner,
+        };
+        PolicyKey { owner, state }
+    }
+    
+    /// Owner id that no agent or loaded table has used yet
+    pub(crate) fn fresh_policy_owner(&mut self) -> u32 {
+        let owner = self.next_policy_owner.max(self.policy_table.owner_floor());
+        self.next_policy_owner = owner + 1;
+        owner
+    }
+    
+    /// Policy a learner plays from in state `key`. Frozen tables are read
+    /// without adding the states they have not seen.
+    #[inline]
+    pub fn play_policy(&self, key: PolicyKey) -> CompactPolicy {
+        if self.freeze_policies {
+            self.policy_table.get_or_initial(key)
+        } else {
+            self.policy_table.get_or_create(key)
+        }
+    }
+    
+    /// An agent's step size, discount and action selection at this timestep
+    #[inline]
+    pub fn learning_params(&self, agent: &Agent) -> LearningParams {
+        LearningParams {
+            alpha: agent.alpha,
+            gamma: agent.gamma,
+            selector: self.exploration.selector(agent.epsilon, self.timestep),
         }
     }
     
@@ -178,8 +246,128 @@ impl Grid {
         idx
     }
     
+    /// Draw every cell's alpha, gamma and epsilon from `hyperparams`.
+    ///
+    /// Must be called before any merge happens, so that merged agents inherit the draws.
+    pub fn set_hyperparameters(&mut self, hyperparams: Hyperparameters) {
+        let cell_count = self.grid_width * self.grid_height;
+        assert_eq!(self.agents.len(), cell_count, "hyperparameters must be set before the first merge");
+        
+        self.hyperparams = hyperparams;
+        let seed = self.seed;
+        self.agents.par_iter_mut().enumerate().for_each(|(idx, agent)| {
+            let mut rng = rng::stream(seed, 0, DOMAIN_HYPERPARAMS, idx as u64);
+            hyperparams.draw(agent, &mut rng);
+        });
+    }
+    
+    /// Redraw every cell's memory length up to the rule's maximum.
+    ///
+    /// Must be called before any merge happens.
+    pub fn set_memory_rule(&mut self, rule: MemoryRule) {
+        let cell_count = self.grid_width * self.grid_height;
+        assert_eq!(self.agents.len(), cell_count, "memory rule must be set before the first merge");
+        
+        self.memory_rule = rule;
+        let seed = self.seed;
+        self.agents.par_iter_mut().enumerate().for_each(|(idx, agent)| {
+            let mut rng = rng::stream(seed, 0, DOMAIN_MEMORY, idx as u64);
+            agent.resize_memory(rule.draw(&mut rng));
+        });
+    }
+    
+    /// Assign initial strategies from `mix`, cell by cell or in square patches.
+    ///
+    /// Must be called before any merge happens.
+    pub fn set_strategies(&mut self, mix: &StrategyMix, layout: StrategyLayout, patch_size: usize) {
+        let cell_count = self.grid_width * self.grid_height;
+        assert_eq!(self.agents.len(), cell_count, "strategies must be set before the first merge");
+        
+        let (seed, width) = (self.seed, self.grid_width);
+        let patch_size = patch_size.max(1);
+        let patches_per_row = width.div_ceil(patch_size);
+        self.agents.par_iter_mut().enumerate().for_each(|(idx, agent)| {
+            let unit = match layout {
+                StrategyLayout::Random => idx,
+                StrategyLayout::Patches => {
+                    let (x, y) = (idx % width, idx / width);
+                    (y / patch_size) * patches_per_row + x / patch_size
+                }
+            };
+            let mut rng = rng::stream(seed, 0, DOMAIN_STRATEGY, unit as u64);
+            agent.strategy = mix.pick(rng.gen());
+        });
+    }
+    
+    /// Select the lattice and neighborhood, rebuilding the organism
+    /// neighborhood index if there is one.
+    ///
+    /// Must be called before any merge happens.
+    pub fn set_topology(&mut self, topology: Topology) {
+        let cell_count = self.grid_width * self.grid_height;
+        assert_eq!(self.agents.len(), cell_count, "topology must be set before the first merge");
+        
+        self.topology = topology;
+        self.set_interaction_scope(self.interaction_scope);
+    }
+    
+    /// Select how opponents are drawn; the organism scope builds the neighborhood index.
+    ///
+    /// Must be called before any merge happens, since the index is grown merge by merge.
+    pub fn set_interaction_scope(&mut self, scope: InteractionScope) {
+        let cell_count = self.grid_width * self.grid_height;
+        assert_eq!(self.agents.len(), cell_count, "interaction scope must be set before the first merge");
+        
+        self.interaction_scope = scope;
+        self.neighborhoods = match scope {
+            InteractionScope::Cell => None,
+            InteractionScope::Organism => {
+                Some(NeighborhoodIndex::new(cell_count, |cell, out| self.get_neighbors(cell, out)))
+            }
+        };
+    }
+    
+    /// Unique roots of all grid cells, in order of first appearance
+    pub(crate) fn active_roots(&self) -> Vec<usize> {
+        let mut seen = vec![false; self.agents.len()];
+        let mut roots = Vec::new();
+        for cell in self.active_mask.iter_ones() {
+            let root = self.root_cache[cell] as usize;
+            if !seen[root] {
+                seen[root] = true;
+                roots.push(root);
+            }
+        }
+        roots
+    }
+    
+    /// Number of grid cells in the organism containing agent `idx`
+    pub fn organism_size(&self, idx: usize) -> u32 {
+        self.organism_sizes[self.find_root(idx)]
+    }
+    
+    /// Grid cells covered by an agent, found by walking its parent tree (`getparents`)
+    pub fn member_cells(&self, idx: usize) -> Vec<usize> {
+        let mut cells = Vec::new();
+        let mut stack = vec![idx];
+        while let Some(i) = stack.pop() {
+            let agent = &self.agents[i];
+            if agent.parent_1 == u32::MAX {
+                cells.push(i);
+            } else {
+                stack.push(agent.parent_1 as usize);
+                stack.push(agent.parent_2 as usize);
+            }
+        }
+        cells
+    }
+    
     /// Generate all interactions for a timestep
     fn generate_interactions(&self) -> Vec<Interaction> {
+        if let Some(index) = &self.neighborhoods {
+            return self.generate_organism_interactions(index);
+        }
+        
         let active_indices: Vec<_> = self.active_mask.iter_ones().collect();
         
         active_indices
@@ -188,13 +376,15 @@ impl Grid {
                 NEIGHBOR_BUFFER.with(|cell| {
                     let mut neighbors = cell.borrow_mut();
                     self.get_neighbors(idx, &mut neighbors);
+                    neighbors.retain(|&n| self.active_mask[n]);
 
                     if neighbors.is_empty() {
                         return Vec::new();
                     }
 
-                    let agent_idx = self.root_cache[idx] as u32;
-                    let opp_idx = neighbors[rand::thread_rng().gen_range(0..neighbors.len())];
+                    let agent_idx = self.root_cache[idx];
+                    let mut rng = rng::stream(self.seed, self.timestep, DOMAIN_PAIRING, idx as u64);
+                    let opp_idx = neighbors[rng.gen_range(0..neighbors.len())];
                     let opp_root = self.root_cache[opp_idx] as u32;
 
                     if opp_root != agent_idx {
@@ -207,11 +397,40 @@ impl Grid {
             .collect()
     }
 
-    /// Process interactions and generate state updates
-    fn process_interactions(&self, interactions: &[Interaction]) -> Vec<StateUpdate> {
-        interactions
+    /// Let every organism play `games_per_organism` games against random
+    /// neighboring organisms, whatever its size
+    fn generate_organism_interactions(&self, index: &NeighborhoodIndex) -> Vec<Interaction> {
+        self.active_roots()
+            .into_par_iter()
+            .flat_map_iter(|root| {
+                let mut opponents = index.neighbor_roots(root, &self.root_cache);
+                opponents.retain(|&opp| self.is_occupied(opp as usize));
+                let mut rng = rng::stream(self.seed, self.timestep, DOMAIN_PAIRING, root as u64);
+                let games = if opponents.is_empty() { 0 } else { self.games_per_organism };
+                (0..games)
+                    .map(|_| Interaction {
+                        agent1_idx: root as u32,
+                        agent2_idx: opponents[rng.gen_range(0..opponents.len())],
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+    
+    /// Process interactions and generate state updates.
+    ///
+    /// Each pairing plays the encounter's rounds against copies of the two
+    /// agents whose memories update between rounds, and yields one pair of
+    /// updates per round. Later rounds read the policies earlier rounds
+    /// learned instead of the start-of-step snapshot. An encounter ends
+    /// early once a round merges or splits either player. Merges and splits
+    /// come back in interaction order alongside the updates.
+    fn process_interactions(&self, interactions: &[Interaction]) -> (Vec<StateUpdate>, Vec<DeferredOp>, JointActionCounts) {
+        let results: Vec<(Vec<StateUpdate>, Vec<DeferredOp>)> = interactions
             .par_iter()
-            .flat_map(|interaction| {
+            .enumerate()
+            .map(|(i, interaction)| {
+                let mut rng = rng::stream(self.seed, self.timestep, DOMAIN_GAME, i as u64);
                 let my_idx = interaction.agent1_idx as usize;
                 let opp_idx = interaction.agent2_idx as usize;
 
@@ -219,77 +438,152 @@ impl Grid {
                 let my_agent = &self.agents[my_idx];
                 let opp_agent = &self.agents[opp_idx];
 
-                // Get current memory states and policies
-                let my_state_hash = my_agent.get_memory_hash(opp_agent.memory_bits, opp_agent.mem_length);
-                let opp_state_hash = opp_agent.get_memory_hash(my_agent.memory_bits, my_agent.mem_length);
-                let my_policy = self.policy_table.get_or_create(my_state_hash);
-                let opp_policy = self.policy_table.get_or_create(opp_state_hash);
-
-                // Choose actions
-                let my_action = my_policy.get_action(self.epsilon);
-                let opp_action = opp_policy.get_action(self.epsilon);
-
-                // Calculate payoffs
-                let my_payoff = self.payoff_table.get(my_action, opp_action);
-                let opp_payoff = self.payoff_table.get(opp_action, my_action);
-
-                // --- Q-value updates ---
-
-                // 1. Determine next state for my_agent
-                let mut next_my_agent = my_agent.clone();
-                next_my_agent.add_to_memory(my_action, opp_action);
-                let next_my_state_hash = next_my_agent.get_memory_hash(opp_agent.memory_bits, opp_agent.mem_length);
-                let next_my_policy = self.policy_table.get_or_create(next_my_state_hash);
-                let next_max_q_my = next_my_policy.q_values.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
-
-                // 2. Determine next state for opp_agent
-                let mut next_opp_agent = opp_agent.clone();
-                next_opp_agent.add_to_memory(opp_action, my_action);
-                let next_opp_state_hash = next_opp_agent.get_memory_hash(my_agent.memory_bits, my_agent.mem_length);
-                let next_opp_policy = self.policy_table.get_or_create(next_opp_state_hash);
-                let next_max_q_opp = next_opp_policy.q_values.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
-
-                // 3. Calculate new Q-values
-                let my_new_q = my_policy.calculate_updated_q_values(my_action, my_payoff, next_max_q_my, self.alpha, self.gamma);
-                let opp_new_q = opp_policy.calculate_updated_q_values(opp_action, opp_payoff, next_max_q_opp, self.alpha, self.gamma);
-
-                // Handle Merge and Split actions
-                if my_action == Action::Merge && opp_action == Action::Merge {
-                    let new_fitness = my_agent.fitness + opp_agent.fitness;
-                    let inherit_from = if my_agent.fitness > opp_agent.fitness { my_idx } else { opp_idx };
-                    self.deferred_ops.push(DeferredOp::Merge {
-                        agent1: my_idx as u32,
-                        agent2: opp_idx as u32,
-                        new_fitness,
-                        inherit_from: inherit_from as u32,
-                    }).ok();
-                } else if my_action == Action::Split && my_agent.is_multicellular() {
-                    self.deferred_ops.push(DeferredOp::Split {
-                        agent: my_idx as u32,
-                        parent1: my_agent.parent_1,
-                        parent2: my_agent.parent_2,
-                    }).ok();
+                // Memories evolve over the encounter; fitness is judged as it began
+                let rounds = self.encounter.rounds.draw(&mut rng);
+                let (mut me, mut opp) = (my_agent.clone(), opp_agent.clone());
+                let mut updates = Vec::with_capacity(2 * rounds as usize);
+                let mut ops = Vec::new();
+                let mut learned = HashMap::new();
+                for round in 1..=rounds {
+                    let (my_action, opp_action, ended) =
+                        self.play_round(&me, &opp, &learned, &mut rng, &mut updates, &mut ops);
+                    if ended || round == rounds {
+                        break;
+                    }
+                    for update in updates[updates.len() - 2..].iter().filter_map(|update| update.learned) {
+                        learned.insert(update.key, update.updated);
+                    }
+                    me.add_to_memory(my_action, opp_action);
+                    opp.add_to_memory(opp_action, my_action);
                 }
-
-                // Create state updates
-                vec![
-                    StateUpdate {
-                        agent_idx: my_idx as u32,
-                        fitness_delta: my_payoff,
-                        action: my_action,
-                        policy_hash: my_state_hash,
-                        new_q_values: my_new_q,
-                    },
-                    StateUpdate {
-                        agent_idx: opp_idx as u32,
-                        fitness_delta: opp_payoff,
-                        action: opp_action,
-                        policy_hash: opp_state_hash,
-                        new_q_values: opp_new_q,
-                    },
-                ]
+                
+                if self.encounter.payoff == RoundPayoff::Average {
+                    let played = (updates.len() / 2) as f32;
+                    for update in &mut updates {
+                        update.fitness_delta /= played;
+                    }
+                }
+                (updates, ops)
             })
-            .collect()
+            .collect();
+        let (updates, ops): (Vec<_>, Vec<_>) = results.into_iter().unzip();
+        let updates: Vec<StateUpdate> = updates.into_iter().flatten().collect();
+        let ops: Vec<DeferredOp> = ops.into_iter().flatten().collect();
+        
+        // Updates come in (player, opponent) pairs, one pair per round
+        let mut games = JointActionCounts::default();
+        for pair in updates.chunks_exact(2) {
+            games.record(pair[0].action, pair[0].opp_action);
+        }
+        (updates, ops, games)
+    }
+
+    /// Play one round between two agents, pushing their state updates.
+    ///
+    /// `learned` holds the policies earlier rounds of the encounter learned,
+    /// which take precedence over the policy table. Returns both actions and
+    /// whether the round merged or split either player, which ends the
+    /// encounter.
+    fn play_round(
+        &self,
+        my_agent: &Agent,
+        opp_agent: &Agent,
+        learned: &HashMap<PolicyKey, CompactPolicy>,
+        rng: &mut SimRng,
+        updates: &mut Vec<StateUpdate>,
+        ops: &mut Vec<DeferredOp>,
+    ) -> (Action, Action, bool) {
+        let (my_idx, opp_idx) = (my_agent.id, opp_agent.id);
+        let policy = |key: PolicyKey| learned.get(&key).copied().unwrap_or_else(|| self.play_policy(key));
+
+        // Get current memory states and policies; fixed strategies have none
+        let my_key = self.policy_key(my_agent, self.state_encoder.encode(my_agent, opp_agent));
+        let opp_key = self.policy_key(opp_agent, self.state_encoder.encode(opp_agent, my_agent));
+        let my_policy = my_agent.strategy.learns().then(|| policy(my_key));
+        let opp_policy = opp_agent.strategy.learns().then(|| policy(opp_key));
+
+        // Choose actions
+        let my_params = self.learning_params(my_agent);
+        let opp_params = self.learning_params(opp_agent);
+        let my_action = match &my_policy {
+            Some(policy) => my_params.selector.choose(policy, rng),
+            None => my_agent.strategy.action(my_agent, rng),
+        };
+        let opp_action = match &opp_policy {
+            Some(policy) => opp_params.selector.choose(policy, rng),
+            None => opp_agent.strategy.action(opp_agent, rng),
+        };
+
+        // Calculate payoffs
+        let my_payoff = self.payoff_table.get(my_action, opp_action);
+        let opp_payoff = self.payoff_table.get(opp_action, my_action);
+
+        // --- Q-value updates ---
+
+        // 1. Learn from my_agent's next state
+        let my_learned = my_policy.filter(|_| !self.freeze_policies).map(|base| {
+            let mut next_my_agent = my_agent.clone();
+            next_my_agent.add_to_memory(my_action, opp_action);
+            let next_my_key = self.policy_key(my_agent, self.state_encoder.encode(&next_my_agent, opp_agent));
+            let next = policy(next_my_key);
+            let updated = self.learning_rule.update(&base, my_action, my_payoff, &next, my_params, rng);
+            PolicyUpdate { key: my_key, base, next, params: my_params, updated }
+        });
+
+        // 2. Learn from opp_agent's next state
+        let opp_learned = opp_policy.filter(|_| !self.freeze_policies).map(|base| {
+            let mut next_opp_agent = opp_agent.clone();
+            next_opp_agent.add_to_memory(opp_action, my_action);
+            let next_opp_key = self.policy_key(opp_agent, self.state_encoder.encode(&next_opp_agent, my_agent));
+            let next = policy(next_opp_key);
+            let updated = self.learning_rule.update(&base, opp_action, opp_payoff, &next, opp_params, rng);
+            PolicyUpdate { key: opp_key, base, next, params: opp_params, updated }
+        });
+
+        // Handle Merge and Split actions
+        let mut ended = false;
+        if self.merge_rule.triggers(my_action, opp_action, rng) {
+            // Like JS, the fitter parent is judged before this game's payoffs
+            let (me, opp) = ((my_idx, my_agent.fitness), (opp_idx, opp_agent.fitness));
+            let (inherit_from, policy_from) = self.merge_rule.pick_parents(me, opp, rng);
+            ops.push(DeferredOp::Merge {
+                agent1: my_idx,
+                agent2: opp_idx,
+                fitness: self.merge_rule.fitness,
+                inherit_from,
+                policy_from,
+                fitter: merge::fitter(me, opp),
+            });
+            ended = true;
+        } else {
+            for (agent_idx, agent, action) in [(my_idx, my_agent, my_action), (opp_idx, opp_agent, opp_action)] {
+                if action == Action::Split && agent.is_multicellular() {
+                    ops.push(DeferredOp::Split {
+                        agent: agent_idx,
+                        parent1: agent.parent_1,
+                        parent2: agent.parent_2,
+                    });
+                    ended = true;
+                }
+            }
+        }
+
+        // Create state updates
+        updates.push(StateUpdate {
+            agent_idx: my_idx,
+            fitness_delta: my_payoff,
+            action: my_action,
+            opp_action,
+            learned: my_learned,
+        });
+        updates.push(StateUpdate {
+            agent_idx: opp_idx,
+            fitness_delta: opp_payoff,
+            action: opp_action,
+            opp_action: my_action,
+            learned: opp_learned,
+        });
+        (my_action, opp_action, ended)
     }
 
     /// Apply state updates to agents in parallel
@@ -306,19 +600,72 @@ impl Grid {
                 for update in agent_updates {
                     agent.fitness += update.fitness_delta;
                     agent.last_action = update.action as u8;
-                    
-                    // The policy table update needs to be handled carefully
-                    // as it's a shared resource.
-                    let new_policy = CompactPolicy { q_values: update.new_q_values };
-                    self.policy_table.update(update.policy_hash, new_policy);
+                    agent.add_to_memory(update.action, update.opp_action);
                 }
             }
         });
+        
+        self.write_policy_updates(updates);
+    }
+    
+    /// Write learned policies back according to `update_aggregation`.
+    ///
+    /// Every update was learned from the policy table as it stood at the
+    /// start of the timestep, or from an earlier round of its encounter. Updates to the same state are grouped in
+    /// interaction order, so the result never depends on thread scheduling.
+    fn write_policy_updates(&mut self, updates: &[StateUpdate]) {
+        let mut group_of: HashMap<PolicyKey, usize> = HashMap::new();
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        for (position, update) in updates.iter().enumerate() {
+            if let Some(learned) = &update.learned {
+                let group = *group_of.entry(learned.key).or_insert_with(|| {
+                    groups.push(Vec::new());
+                    groups.len() - 1
+                });
+                groups[group].push(position);
+            }
+        }
+        self.pass_stats.shared_state_updates = groups.iter().map(|group| group.len() - 1).sum();
+        
+        let learned = |position: usize| updates[position].learned.unwrap();
+        let combined: Vec<(PolicyKey, CompactPolicy)> = groups
+            .par_iter()
+            .map(|group| {
+                let first = learned(group[0]);
+                let policy = match self.update_aggregation {
+                    UpdateAggregation::LastWrite => learned(*group.last().unwrap()).updated,
+                    UpdateAggregation::Ordered => group[1..].iter().fold(first.updated, |policy, &position| {
+                        let (update, learned) = (&updates[position], learned(position));
+                        let mut rng = rng::stream(self.seed, self.timestep, DOMAIN_POLICY_UPDATE, position as u64);
+                        let reward = self.payoff_table.get(update.action, update.opp_action);
+                        self.learning_rule.update(&policy, update.action, reward, &learned.next, learned.params, &mut rng)
+                    }),
+                    UpdateAggregation::Sum | UpdateAggregation::Mean => {
+                        // Later rounds of an encounter learned from earlier rounds'
+                        // results, so measure each change from its own base
+                        let snapshot = self.policy_table.get_or_create(first.key);
+                        let changes: Vec<_> = group
+                            .iter()
+                            .map(|&position| {
+                                let learned = learned(position);
+                                (updates[position].action, rebase(&snapshot, &learned.base, &learned.updated))
+                            })
+                            .collect();
+                        self.update_aggregation.combine(&snapshot, &changes)
+                    }
+                };
+                (first.key, policy)
+            })
+            .collect();
+        for (key, policy) in combined {
+            self.policy_table.update(key, policy);
+        }
     }
     
     /// Run one timestep of the simulation
     pub fn step(&mut self) {
         self.pass_stats.reset();
+        self.policy_table.set_clock(self.timestep);
 
         // === Pass 1: Update Root Cache ===
         let start = Instant::now();
@@ -333,9 +680,11 @@ impl Grid {
 
         // === Pass 3: Process Interactions ===
         let start = Instant::now();
-        let updates = self.process_interactions(&interactions);
+        let (updates, ops, games) = self.process_interactions(&interactions);
         self.pass_stats.interaction_processing_time = start.elapsed().as_micros();
         self.pass_stats.num_updates = updates.len();
+        self.pass_stats.num_deferred_ops = ops.len() + self.deferred_ops.len();
+        self.record_games(games);
 
         // === Pass 4: Apply State Updates ===
         let start = Instant::now();
@@ -344,8 +693,51 @@ impl Grid {
 
         // === Pass 5: Apply Deferred Operations ===
         let start = Instant::now();
-        self.apply_deferred_operations_parallel();
+        self.apply_deferred_operations_parallel(ops);
         self.pass_stats.deferred_op_time = start.elapsed().as_micros();
+        
+        // === Pass 6: Migrate onto Vacant Cells ===
+        let start = Instant::now();
+        self.pass_stats.migrations = self.migrate();
+        self.pass_stats.migration_time = start.elapsed().as_micros();
+        
+        // === Pass 7: Evolutionary Update ===
+        if let Some(evolution) = self.evolution.filter(|evolution| (self.timestep + 1).is_multiple_of(evolution.period)) {
+            let start = Instant::now();
+            self.pass_stats.replacements = self.evolve(evolution);
+            self.pass_stats.evolution_time = start.elapsed().as_micros();
+        }
+        
+        self.record_arena_stats();
+        self.trim_policy_table();
+        self.timestep += 1;
+    }
+    
+    /// Record this timestep's joint actions and add their stress to the running total
+    pub(crate) fn record_games(&mut self, games: JointActionCounts) {
+        self.stress += games.stress();
+        self.pass_stats.joint_actions = games;
+    }
+    
+    /// Record agent arena size, reuse and memory in the pass statistics
+    pub(crate) fn record_arena_stats(&mut self) {
+        let live_agents = self.agents.len() - self.free_agents.len();
+        self.peak_live_agents = self.peak_live_agents.max(live_agents);
+        
+        self.pass_stats.agent_slots = self.agents.len();
+        self.pass_stats.live_agents = live_agents;
+        self.pass_stats.peak_live_agents = self.peak_live_agents;
+        self.pass_stats.agent_memory_bytes = self.agents.capacity() * std::mem::size_of::<Agent>()
+            + self.active_mask.capacity() / 8
+            + (self.root_cache.capacity() + self.organism_sizes.capacity() + self.free_agents.capacity()) * 4;
+    }
+
+    /// Evict policies down to the table's capacity and record its size and churn
+    pub(crate) fn trim_policy_table(&mut self) {
+        self.pass_stats.policy_evictions = self.policy_table.trim();
+        self.pass_stats.policy_inserts = self.policy_table.take_inserts();
+        self.pass_stats.policy_states = self.policy_table.len();
+        self.pass_stats.policy_memory_bytes = self.policy_table.memory_bytes();
     }
 
     /// Update the root cache
@@ -363,27 +755,34 @@ impl Grid {
     }
     
     /// Apply merge and split operations in parallel
-    fn apply_deferred_operations_parallel(&mut self) {
-        let mut ops = Vec::new();
-        while let Some(op) = self.deferred_ops.pop() {
-            ops.push(op);
-        }
-
-        let next_agent_id = AtomicU32::new(self.agents.len() as u32);
+    fn apply_deferred_operations_parallel(&mut self, mut ops: Vec<DeferredOp>) {
+        ops.append(&mut self.deferred_ops);
+        
+        // Commit in a fixed order whichever game or caller queued an op.
+        // The key covers every field, so duplicate merges of one pair that
+        // picked different parents still commit in the same order.
+        ops.sort_unstable_by_key(|op| match *op {
+            DeferredOp::Merge { agent1, agent2, fitness, inherit_from, policy_from, fitter } => {
+                (0, agent1, agent2, inherit_from, policy_from, fitter, fitness as u8)
+            }
+            DeferredOp::Split { agent, parent1, parent2 } => (1, agent, parent1, parent2, 0, 0, 0),
+        });
 
         // --- Phase 1: Parallel Collection ---
         let final_ops: Vec<_> = ops.par_iter().map(|op| {
             match *op {
-                DeferredOp::Merge { agent1, agent2, new_fitness, inherit_from } => {
+                DeferredOp::Merge { agent1, agent2, fitness, inherit_from, policy_from, fitter } => {
                     if agent1 as usize >= self.agents.len() || agent2 as usize >= self.agents.len() ||
                        self.agents[agent1 as usize].child != u32::MAX || self.agents[agent2 as usize].child != u32::MAX {
                         return FinalOp::NoOp;
                     }
 
-                    let new_id = next_agent_id.fetch_add(1, Ordering::Relaxed);
                     let mut new_agent = self.agents[inherit_from as usize].clone();
-                    new_agent.id = new_id;
-                    new_agent.fitness = new_fitness;
+                    new_agent.policy_owner = self.agents[policy_from as usize].policy_owner;
+                    new_agent.fitness = fitness.combine(
+                        self.agents[agent1 as usize].fitness,
+                        self.agents[agent2 as usize].fitness,
+                    );
                     new_agent.parent_1 = agent1;
                     new_agent.parent_2 = agent2;
                     new_agent.generation += 1;
@@ -392,19 +791,13 @@ impl Grid {
                         new_agent,
                         parent1_idx: agent1,
                         parent2_idx: agent2,
-                        new_agent_id: new_id,
+                        fitter,
                     }
                 }
                 DeferredOp::Split { agent, parent1, parent2 } => {
                     if parent1 != u32::MAX && parent2 != u32::MAX &&
                        (parent1 as usize) < self.agents.len() && (parent2 as usize) < self.agents.len() {
-                        
-                        let fitness = self.agents[agent as usize].fitness;
-                        FinalOp::Split {
-                            parent1_idx: parent1,
-                            parent2_idx: parent2,
-                            new_fitness: fitness / 2.0,
-                        }
+                        FinalOp::Split { agent_idx: agent, parent1_idx: parent1, parent2_idx: parent2 }
                     } else {
                         FinalOp::NoOp
                     }
@@ -419,29 +812,124 @@ impl Grid {
         self.agents.reserve(new_agent_count);
         self.active_mask.reserve(new_agent_count);
         self.root_cache.reserve(new_agent_count);
+        self.organism_sizes.reserve(new_agent_count);
 
         for op in final_ops {
             match op {
-                FinalOp::Merge { new_agent, parent1_idx, parent2_idx, new_agent_id } => {
-                    self.agents[parent1_idx as usize].child = new_agent_id;
-                    self.agents[parent2_idx as usize].child = new_agent_id;
-                    self.agents.push(new_agent);
-                    self.active_mask.push(false);
-                    self.root_cache.push(0);
+                FinalOp::Merge { new_agent, parent1_idx, parent2_idx, fitter } => {
+                    // An agent can only join one merge per timestep
+                    if self.agents[parent1_idx as usize].child != u32::MAX ||
+                       self.agents[parent2_idx as usize].child != u32::MAX {
+                        continue;
+                    }
+                    
+                    self.attach_merged(new_agent, fitter);
                 }
-                FinalOp::Split { parent1_idx, parent2_idx, new_fitness } => {
-                    // For simplicity, we are not re-inserting them into the grid here.
-                    // This part of the logic might need refinement if splits are common.
-                    self.agents[parent1_idx as usize].child = u32::MAX;
-                    self.agents[parent2_idx as usize].child = u32::MAX;
-                    self.agents[parent1_idx as usize].fitness = new_fitness;
-                    self.agents[parent2_idx as usize].fitness = new_fitness;
+                FinalOp::Split { agent_idx, parent1_idx, parent2_idx } => {
+                    // Skip splits of organisms that merged again earlier in this commit
+                    let agent = &self.agents[agent_idx as usize];
+                    if agent.parent_1 == parent1_idx && agent.parent_2 == parent2_idx {
+                        self.split_agent(agent_idx as usize, self.split_fitness);
+                    }
                 }
                 FinalOp::NoOp => {}
             }
         }
     }
     
+    /// Store a merged agent whose `parent_1`/`parent_2` are set, making it
+    /// the new root of both parents. `fitter` is the parent that was fitter
+    /// before the merging game. Reuses a retired slot when one is free.
+    /// Returns its id.
+    ///
+    /// The root cache must still map the parents' cells to the parents; it
+    /// maps them to the new agent afterwards.
+    pub(crate) fn attach_merged(&mut self, mut new_agent: Agent, fitter: u32) -> u32 {
+        let reused = self.free_agents.pop();
+        let new_id = reused.unwrap_or(self.agents.len() as u32);
+        let (parent1, parent2) = (new_agent.parent_1, new_agent.parent_2);
+        new_agent.id = new_id;
+        new_agent.child = u32::MAX;
+        let mut rng = rng::stream(self.seed, self.timestep, DOMAIN_MUTATION, new_id as u64);
+        self.hyperparams.mutate(&mut new_agent, &mut rng);
+        let mem_length = self.memory_rule.merged(
+            new_agent.mem_length,
+            self.agents[fitter as usize].mem_length,
+            &self.agents[parent1 as usize],
+            &self.agents[parent2 as usize],
+        );
+        new_agent.resize_memory(mem_length);
+        
+        if let Some(index) = &mut self.neighborhoods {
+            let root_cache = &self.root_cache;
+            index.on_merge(new_id as usize, parent1 as usize, parent2 as usize, |cell| {
+                let root = root_cache[cell as usize];
+                root == parent1 || root == parent2
+            });
+        }
+        let size = self.organism_sizes[parent1 as usize] + self.organism_sizes[parent2 as usize];
+        
+        self.agents[parent1 as usize].child = new_id;
+        self.agents[parent2 as usize].child = new_id;
+        if reused.is_some() {
+            self.pass_stats.reclaimed_agents += 1;
+            self.agents[new_id as usize] = new_agent;
+            self.organism_sizes[new_id as usize] = size;
+            self.root_cache[new_id as usize] = new_id;
+        } else {
+            self.agents.push(new_agent);
+            self.organism_sizes.push(size);
+            self.active_mask.push(false);
+            self.root_cache.push(new_id);
+        }
+        for cell in self.member_cells(new_id as usize) {
+            self.root_cache[cell] = new_id;
+        }
+        new_id
+    }
+    
+    /// Dissolve a root super-agent back into its two parents (algorithm.md §5.2).
+    ///
+    /// The parents become roots again and take over the super-agent's memory
+    /// and Q-table; fitness follows `rule`. Each parent's memory length may
+    /// then mutate under the memory rule. The super-agent is retired and
+    /// its slot goes on the free list, and the root cache maps the cells back
+    /// to the parents.
+    /// Returns `false` if `idx` is not a live multicellular root.
+    pub(crate) fn split_agent(&mut self, idx: usize, rule: SplitFitness) -> bool {
+        let dissolved = self.agents[idx].clone();
+        if dissolved.child != u32::MAX || dissolved.parent_1 == u32::MAX || dissolved.parent_2 == u32::MAX {
+            return false;
+        }
+        
+        let fitness = match rule {
+            SplitFitness::Inherit => dissolved.fitness,
+            SplitFitness::Halve => dissolved.fitness / 2.0,
+        };
+        for parent_idx in [dissolved.parent_1, dissolved.parent_2] {
+            let parent = &mut self.agents[parent_idx as usize];
+            parent.child = u32::MAX;
+            parent.fitness = fitness;
+            parent.policy_owner = dissolved.policy_owner;
+            parent.strategy = dissolved.strategy;
+            parent.alpha = dissolved.alpha;
+            parent.gamma = dissolved.gamma;
+            parent.epsilon = dissolved.epsilon;
+            let mut rng = rng::stream(self.seed, self.timestep, DOMAIN_MUTATION, parent_idx as u64);
+            self.hyperparams.mutate(parent, &mut rng);
+            parent.mem_length = self.memory_rule.mutate(dissolved.mem_length, &mut rng);
+            parent.set_memory(dissolved.memory_bits, dissolved.mem_length);
+        }
+        self.agents[idx].retire();
+        self.free_agents.push(idx as u32);
+        for parent_idx in [dissolved.parent_1, dissolved.parent_2] {
+            for cell in self.member_cells(parent_idx as usize) {
+                self.root_cache[cell] = parent_idx;
+            }
+        }
+        true
+    }
+    
     /// Get statistics for the current state
     pub fn get_statistics(&self) -> Statistics {
         let mut stats = Statistics::default();
@@ -477,7 +965,12 @@ impl Grid {
                         local_stats.unicellular_fitness += agent.fitness as f64;
                     }
                     
+                    let by_strategy = &mut local_stats.by_strategy[agent.strategy as usize];
+                    by_strategy.cells += 1;
+                    by_strategy.fitness += agent.fitness as f64;
+                    
                     if agent.last_action == Action::Cooperate as u8 {
+                        by_strategy.cooperators += 1;
                         if agent.is_multicellular() {
                             local_stats.multicellular_cooperation += 1;
                         } else {
@@ -490,6 +983,19 @@ impl Grid {
             })
             .collect();
         
+        // Organism counts come from the exact membership sizes
+        let roots = self.active_roots();
+        stats.organisms = roots.len();
+        stats.max_organism_size = roots.iter().map(|&root| self.organism_sizes[root]).max().unwrap_or(0);
+        stats.stress = self.stress;
+        let organisms = || roots.iter().map(|&root| &self.agents[root]);
+        stats.alpha = ParamSummary::of(organisms().map(|agent| agent.alpha));
+        stats.gamma = ParamSummary::of(organisms().map(|agent| agent.gamma));
+        stats.epsilon = ParamSummary::of(organisms().map(|agent| agent.epsilon));
+        for agent in organisms() {
+            stats.mem_lengths[agent.mem_length as usize] += 1;
+        }
+        
         // Combine partial statistics
         for partial in partial_stats {
             stats.total_agents += partial.total_agents;
@@ -500,6 +1006,11 @@ impl Grid {
             stats.multicellular_fitness += partial.multicellular_fitness;
             stats.unicellular_cooperation += partial.unicellular_cooperation;
             stats.multicellular_cooperation += partial.multicellular_cooperation;
+            for (total, part) in stats.by_strategy.iter_mut().zip(&partial.by_strategy) {
+                total.cells += part.cells;
+                total.fitness += part.fitness;
+                total.cooperators += part.cooperators;
+            }
         }
         
         stats
@@ -510,11 +1021,85 @@ impl Grid {
 pub struct PassStatistics {
     pub num_interactions: usize,
     pub num_updates: usize,
+    pub num_deferred_ops: usize,
     pub cache_update_time: u128,
     pub interaction_generation_time: u128,
     pub interaction_processing_time: u128,
     pub state_update_time: u128,
     pub deferred_op_time: u128,
+    pub migration_time: u128,
+    pub migrations: usize,
+    pub evolution_time: u128,
+    pub replacements: usize,
+    
+    // Agent arena
+    pub agent_slots: usize,
+    pub live_agents: usize,
+    pub peak_live_agents: usize,
+    pub reclaimed_agents: usize,
+    pub agent_memory_bytes: usize,
+    
+    // Policy table
+    pub policy_states: usize,
+    pub policy_inserts: usize,
+    pub policy_evictions: usize,
+    pub policy_memory_bytes: usize,
+    // Policy updates to a state another game already updated this timestep
+    pub shared_state_updates: usize,
+    
+    // Games played this timestep
+    pub joint_actions: JointActionCounts,
+}
+
+/// Games played per unordered pair of actions
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct JointActionCounts {
+    // Indexed by (lower action, higher action)
+    counts: [[u32; 4]; 4],
+}
+
+impl JointActionCounts {
+    /// Every unordered action pair, in CSV column order
+    pub const PAIRS: [(Action, Action); 10] = [
+        (Action::Cooperate, Action::Cooperate),
+        (Action::Cooperate, Action::Defect),
+        (Action::Cooperate, Action::Merge),
+        (Action::Cooperate, Action::Split),
+        (Action::Defect, Action::Defect),
+        (Action::Defect, Action::Merge),
+        (Action::Defect, Action::Split),
+        (Action::Merge, Action::Merge),
+        (Action::Merge, Action::Split),
+        (Action::Split, Action::Split),
+    ];
+    
+    pub fn record(&mut self, a: Action, b: Action) {
+        let (lo, hi) = if a as u8 <= b as u8 { (a, b) } else { (b, a) };
+        self.counts[lo as usize][hi as usize] += 1;
+    }
+    
+    pub fn get(&self, a: Action, b: Action) -> u32 {
+        let (lo, hi) = if a as u8 <= b as u8 { (a, b) } else { (b, a) };
+        self.counts[lo as usize][hi as usize]
+    }
+    
+    /// Change in the JS `stress` counter from these games: +1 when one side
+    /// cooperated or merged while the other defected or split, -1 when both
+    /// cooperated or merged
+    pub fn stress(&self) -> i64 {
+        let nice = |action: Action| matches!(action, Action::Cooperate | Action::Merge);
+        Self::PAIRS
+            .iter()
+            .map(|&(a, b)| {
+                let games = self.get(a, b) as i64;
+                match (nice(a), nice(b)) {
+                    (true, true) => -games,
+                    (false, false) => 0,
+                    _ => games,
+                }
+            })
+            .sum()
+    }
 }
 
 impl PassStatistics {
@@ -533,9 +1118,47 @@ pub struct Statistics {
     pub multicellular_fitness: f64,
     pub unicellular_cooperation: usize,
     pub multicellular_cooperation: usize,
+    pub organisms: usize,
+    pub max_organism_size: u32,
+    /// Running JS `stress` total since the start of the run
+    pub stress: i64,
+    /// Learning hyperparameters across organisms
+    pub alpha: ParamSummary,
+    pub gamma: ParamSummary,
+    pub epsilon: ParamSummary,
+    /// Organisms per memory length, indexed by `mem_length`
+    pub mem_lengths: [usize; MAX_MEM_LENGTH as usize + 1],
+    /// Cells per strategy of their organism, indexed by `Strategy as usize`
+    pub by_strategy: [StrategyStats; Strategy::ALL.len()],
     pub pass_stats: PassStatistics,
 }
 
+/// Cell counts for one strategy
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StrategyStats {
+    pub cells: usize,
+    pub fitness: f64,
+    pub cooperators: usize,
+}
+
+impl StrategyStats {
+    pub fn avg_fitness(&self) -> f64 {
+        if self.cells > 0 {
+            self.fitness / self.cells as f64
+        } else {
+            0.0
+        }
+    }
+    
+    pub fn cooperation_rate(&self) -> f64 {
+        if self.cells > 0 {
+            self.cooperators as f64 / self.cells as f64
+        } else {
+            0.0
+        }
+    }
+}
+
 impl Statistics {
     pub fn avg_fitness(&self) -> f64 {
         if self.total_agents > 0 {
@@ -569,6 +1192,24 @@ impl Statistics {
         }
     }
     
+    pub fn mean_mem_length(&self) -> f64 {
+        let organisms: usize = self.mem_lengths.iter().sum();
+        if organisms > 0 {
+            let total: usize = self.mem_lengths.iter().enumerate().map(|(length, &count)| length * count).sum();
+            total as f64 / organisms as f64
+        } else {
+            0.0
+        }
+    }
+    
+    pub fn mean_organism_size(&self) -> f64 {
+        if self.organisms > 0 {
+            self.total_agents as f64 / self.organisms as f64
+        } else {
+            0.0
+        }
+    }
+    
     pub fn multicellular_cooperation_rate(&self) -> f64 {
         if self.multicellular_agents > 0 {
             self.multicellular_cooperation as f64 / self.multicellular_agents as f64
@@ -585,11 +1226,11 @@ enum FinalOp {
         new_agent: Agent,
         parent1_idx: u32,
         parent2_idx: u32,
-        new_agent_id: u32,
+        fitter: u32,
     },
     Split {
+        agent_idx: u32,
         parent1_idx: u32,
         parent2_idx: u32,
-        new_fitness: f32,
     },
 }
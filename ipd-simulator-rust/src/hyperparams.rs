@@ -0,0 +1,179 @@
+use crate::agent::Agent;
+use rand::Rng;
+use std::fmt;
+use std::str::FromStr;
+
+/// Distribution an agent's alpha, gamma or epsilon is drawn from.
+///
+/// Parsed from `VALUE`, `uniform:LOW:HIGH` or `normal:MEAN:SD`; draws are
+/// clamped to [0, 1].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParamDistribution {
+    Fixed(f32),
+    Uniform(f32, f32),
+    Normal(f32, f32),
+}
+
+impl ParamDistribution {
+    pub fn sample<R: Rng>(self, rng: &mut R) -> f32 {
+        let value = match self {
+            ParamDistribution::Fixed(value) => value,
+            ParamDistribution::Uniform(low, high) if high > low => rng.gen_range(low..high),
+            ParamDistribution::Uniform(low, _) => low,
+            ParamDistribution::Normal(mean, sd) => mean + sd * standard_normal(rng),
+        };
+        value.clamp(0.0, 1.0)
+    }
+}
+
+impl FromStr for ParamDistribution {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let number = |text: &str| -> Result<f32, String> {
+            let value: f32 = text.trim().parse().map_err(|_| format!("invalid number \"{}\" in \"{}\"", text, spec))?;
+            if value.is_finite() {
+                Ok(value)
+            } else {
+                Err(format!("\"{}\" is not a finite number", text))
+            }
+        };
+
+        let parts: Vec<&str> = spec.split(':').collect();
+        match parts.as_slice() {
+            [value] => Ok(ParamDistribution::Fixed(number(value)?)),
+            ["uniform", low, high] => {
+                let (low, high) = (number(low)?, number(high)?);
+                if low > high {
+                    return Err(format!("uniform range {}..{} is empty", low, high));
+                }
+                Ok(ParamDistribution::Uniform(low, high))
+            }
+            ["normal", mean, sd] => {
+                let (mean, sd) = (number(mean)?, number(sd)?);
+                if sd < 0.0 {
+                    return Err(format!("normal sd {} is negative", sd));
+                }
+                Ok(ParamDistribution::Normal(mean, sd))
+            }
+            _ => Err(format!("expected VALUE, uniform:LOW:HIGH or normal:MEAN:SD, got \"{}\"", spec)),
+        }
+    }
+}
+
+impl fmt::Display for ParamDistribution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParamDistribution::Fixed(value) => write!(f, "{}", value),
+            ParamDistribution::Uniform(low, high) => write!(f, "uniform:{}:{}", low, high),
+            ParamDistribution::Normal(mean, sd) => write!(f, "normal:{}:{}", mean, sd),
+        }
+    }
+}
+
+/// Box-Muller transform
+fn standard_normal<R: Rng>(rng: &mut R) -> f32 {
+    let u1 = 1.0 - rng.gen::<f32>(); // (0, 1], keeps ln finite
+    let u2 = rng.gen::<f32>();
+    (-2.0 * u1.ln()).sqrt() * (std::f32::consts::TAU * u2).cos()
+}
+
+/// Per-agent learning hyperparameters: initial distributions and how much
+/// they drift when passed on by a merge or split
+#[derive(Debug, Clone, Copy)]
+pub struct Hyperparameters {
+    pub alpha: ParamDistribution,
+    pub gamma: ParamDistribution,
+    pub epsilon: ParamDistribution,
+    /// Standard deviation of the Gaussian noise added on inheritance (0 = exact copy)
+    pub mutation: f32,
+}
+
+impl Default for Hyperparameters {
+    fn default() -> Self {
+        Self {
+            alpha: ParamDistribution::Fixed(0.2),
+            gamma: ParamDistribution::Fixed(0.95),
+            epsilon: ParamDistribution::Fixed(0.1),
+            mutation: 0.0,
+        }
+    }
+}
+
+impl Hyperparameters {
+    /// Give a new agent its own draw from each distribution
+    pub fn draw<R: Rng>(&self, agent: &mut Agent, rng: &mut R) {
+        agent.alpha = self.alpha.sample(rng);
+        agent.gamma = self.gamma.sample(rng);
+        agent.epsilon = self.epsilon.sample(rng);
+    }
+
+    /// Perturb inherited values, if mutation is enabled
+    pub fn mutate<R: Rng>(&self, agent: &mut Agent, rng: &mut R) {
+        if self.mutation <= 0.0 {
+            return;
+        }
+        for value in [&mut agent.alpha, &mut agent.gamma, &mut agent.epsilon] {
+            *value = (*value + self.mutation * standard_normal(rng)).clamp(0.0, 1.0);
+        }
+    }
+}
+
+/// Spread of one hyperparameter across the population
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ParamSummary {
+    pub mean: f64,
+    pub std: f64,
+    pub min: f32,
+    pub max: f32,
+}
+
+impl ParamSummary {
+    pub fn of(values: impl Iterator<Item = f32>) -> Self {
+        let (mut count, mut sum, mut sum_sq) = (0usize, 0.0f64, 0.0f64);
+        let (mut min, mut max) = (f32::INFINITY, f32::NEG_INFINITY);
+        for value in values {
+            count += 1;
+            sum += value as f64;
+            sum_sq += value as f64 * value as f64;
+            min = min.min(value);
+            max = max.max(value);
+        }
+        if count == 0 {
+            return Self::default();
+        }
+        let mean = sum / count as f64;
+        Self { mean, std: (sum_sq / count as f64 - mean * mean).max(0.0).sqrt(), min, max }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_parse_distributions() {
+        assert_eq!("0.3".parse(), Ok(ParamDistribution::Fixed(0.3)));
+        assert_eq!("uniform:0.1:0.5".parse(), Ok(ParamDistribution::Uniform(0.1, 0.5)));
+        assert_eq!("normal:0.2:0.05".parse(), Ok(ParamDistribution::Normal(0.2, 0.05)));
+        assert!("uniform:0.5:0.1".parse::<ParamDistribution>().is_err());
+        assert!("normal:0.2".parse::<ParamDistribution>().is_err());
+        assert!("gamma:1:2".parse::<ParamDistribution>().is_err());
+    }
+
+    #[test]
+    fn test_samples_stay_in_range() {
+        let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(0);
+        let uniform = ParamDistribution::Uniform(0.1, 0.5);
+        let wide = ParamDistribution::Normal(0.5, 10.0);
+        for _ in 0..1000 {
+            let u = uniform.sample(&mut rng);
+            assert!((0.1..0.5).contains(&u));
+            assert!((0.0..=1.0).contains(&wide.sample(&mut rng)));
+        }
+
+        let summary = ParamSummary::of([0.0, 1.0].into_iter());
+        assert_eq!((summary.mean, summary.std, summary.min, summary.max), (0.5, 0.5, 0.0, 1.0));
+    }
+}
@@ -0,0 +1,164 @@
+use crate::agent::{Action, Agent, CompactPolicy, MAX_MEM_LENGTH};
+use crate::policy_table::PolicyTable;
+use crate::rng::SimRng;
+use crate::state::StateEncoder;
+use crate::strategy::Strategy;
+use rand::SeedableRng;
+use rayon::prelude::*;
+use std::error::Error;
+use std::path::Path;
+
+/// Classic strategies that learned policies are compared with
+pub const CLASSIFIED: [Strategy; 5] = [Strategy::Tft, Strategy::Wsls, Strategy::AllD, Strategy::AllC, Strategy::Grim];
+
+/// Greedy actions of the learned states with one memory length
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AgreementCounts {
+    pub states: usize,
+    /// States whose greedy action matches each of `CLASSIFIED`
+    pub agree: [usize; CLASSIFIED.len()],
+    pub merge: usize,
+    pub split: usize,
+}
+
+impl AgreementCounts {
+    fn add(&mut self, other: &AgreementCounts) {
+        self.states += other.states;
+        for (total, part) in self.agree.iter_mut().zip(&other.agree) {
+            *total += part;
+        }
+        self.merge += other.merge;
+        self.split += other.split;
+    }
+
+    /// `count` as a fraction of the states
+    pub fn fraction(&self, count: usize) -> f64 {
+        if self.states > 0 {
+            count as f64 / self.states as f64
+        } else {
+            0.0
+        }
+    }
+}
+
+/// What the policy table learned: per memory length, how often the greedy
+/// action agrees with each classic strategy and how often it is Merge or Split.
+///
+/// Only states updated at least once count; the others still hold their
+/// random initial Q-values.
+#[derive(Debug, Clone)]
+pub struct PolicyReport {
+    pub by_mem_length: [AgreementCounts; MAX_MEM_LENGTH as usize + 1],
+    pub unvisited: usize,
+}
+
+impl PolicyReport {
+    pub fn analyze(table: &PolicyTable, encoder: StateEncoder) -> Self {
+        let empty = || Self { by_mem_length: [AgreementCounts::default(); MAX_MEM_LENGTH as usize + 1], unvisited: 0 };
+        table
+            .entries()
+            .par_iter()
+            .fold(empty, |mut report, (key, policy)| {
+                report.record(encoder.decode(key.state), policy);
+                report
+            })
+            .reduce(empty, |mut a, b| {
+                for (total, part) in a.by_mem_length.iter_mut().zip(&b.by_mem_length) {
+                    total.add(part);
+                }
+                a.unvisited += b.unvisited;
+                a
+            })
+    }
+
+    fn record(&mut self, (memory_bits, mem_length): (u32, u8), policy: &CompactPolicy) {
+        if policy.visits.iter().all(|&n| n == 0) || mem_length == 0 || mem_length > MAX_MEM_LENGTH {
+            self.unvisited += 1;
+            return;
+        }
+
+        // Fixed strategies read only the agent's memory; the classified ones never draw
+        let mut rng = SimRng::seed_from_u64(0);
+        let mut agent = Agent::new(0, &mut rng);
+        agent.memory_bits = memory_bits;
+        agent.mem_length = mem_length;
+
+        let greedy = policy.greedy_action();
+        let counts = &mut self.by_mem_length[mem_length as usize];
+        counts.states += 1;
+        for (agree, strategy) in counts.agree.iter_mut().zip(CLASSIFIED) {
+            if strategy.action(&agent, &mut rng) == greedy {
+                *agree += 1;
+            }
+        }
+        match greedy {
+            Action::Merge => counts.merge += 1,
+            Action::Split => counts.split += 1,
+            _ => {}
+        }
+    }
+
+    /// Counts over every memory length
+    pub fn total(&self) -> AgreementCounts {
+        let mut total = AgreementCounts::default();
+        for counts in &self.by_mem_length {
+            total.add(counts);
+        }
+        total
+    }
+
+    /// Write one CSV row per memory length that has learned states, then an `all` row
+    pub fn write_csv(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let mut writer = csv::Writer::from_path(path)?;
+        let mut header = vec!["mem_length".to_string(), "states".to_string()];
+        header.extend(CLASSIFIED.iter().map(|strategy| format!("{}_agreement", strategy.name())));
+        header.extend(["merge_fraction", "split_fraction"].map(String::from));
+        writer.write_record(&header)?;
+
+        let rows = self
+            .by_mem_length
+            .iter()
+            .enumerate()
+            .filter(|(_, counts)| counts.states > 0)
+            .map(|(length, counts)| (length.to_string(), *counts))
+            .chain([("all".to_string(), self.total())]);
+        for (label, counts) in rows {
+            let mut row = vec![label, counts.states.to_string()];
+            row.extend(counts.agree.iter().map(|&agree| counts.fraction(agree).to_string()));
+            row.push(counts.fraction(counts.merge).to_string());
+            row.push(counts.fraction(counts.split).to_string());
+            writer.write_record(&row)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::PolicyKey;
+
+    #[test]
+    fn test_tft_policy_is_recognized() {
+        let table = PolicyTable::new(0);
+        let encoder = StateEncoder::Own;
+        // Every one-game history, with the greedy action set to what TFT plays
+        for pair in 0..16u64 {
+            let key = PolicyKey { owner: 0, state: (1 << 56) | pair };
+            let mut policy = table.get_or_create(key);
+            let tft = if matches!(Action::from_u8(pair as u8), Action::Cooperate | Action::Merge) { 0 } else { 1 };
+            policy.q_values = [0.0; 4];
+            policy.q_values[tft] = 1.0;
+            policy.visits = [1, 0, 0, 0];
+            table.update(key, policy);
+        }
+        table.get_or_create(PolicyKey { owner: 0, state: (2 << 56) | 0xFF });
+
+        let report = PolicyReport::analyze(&table, encoder);
+        let counts = report.by_mem_length[1];
+        assert_eq!((counts.states, report.unvisited), (16, 1));
+        assert_eq!(counts.agree[0], 16);
+        assert!(counts.agree[2] < 16 && counts.merge == 0);
+    }
+}
@@ -0,0 +1,219 @@
+use crate::agent::{Action, CompactPolicy};
+use crate::exploration::ActionSelector;
+use rand::Rng;
+
+/// How a policy's Q-values learn from one game
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum LearningRule {
+    /// Off-policy: bootstrap from the best next action (JS `updatePolicy`)
+    QLearning,
+    /// Bootstrap from one next action sampled from the exploration policy.
+    ///
+    /// Not SARSA proper: the action the agent really plays next belongs to a
+    /// later game, so this is a single-sample estimate of Expected SARSA.
+    SampledSarsa,
+    /// Bootstrap from the expected next value under the exploration policy
+    ExpectedSarsa,
+    /// Two estimators per state: one picks the next action, the other values it
+    DoubleQ,
+}
+
+/// How several games' updates to one state in a timestep reach the policy table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum UpdateAggregation {
+    /// Each update learns from the timestep's snapshot; the last game in interaction order wins
+    LastWrite,
+    /// Updates are replayed in interaction order, each learning from the previous result
+    Ordered,
+    /// The snapshot moves by the sum of every update's change
+    Sum,
+    /// The snapshot moves by the mean change of the updates to each action
+    Mean,
+}
+
+impl UpdateAggregation {
+    /// Combine `updates`, each learned on its own from `snapshot` for the
+    /// given action. Used for `Sum` and `Mean`; visit counts always add up.
+    pub fn combine(self, snapshot: &CompactPolicy, updates: &[(Action, CompactPolicy)]) -> CompactPolicy {
+        let mut per_action = [0u32; 4];
+        for &(action, _) in updates {
+            per_action[action as usize] += 1;
+        }
+        let mut combined = *snapshot;
+        for (action, updated) in updates {
+            let weight = match self {
+                UpdateAggregation::Mean => 1.0 / per_action[*action as usize] as f32,
+                _ => 1.0,
+            };
+            for i in 0..4 {
+                combined.q_values[i] += weight * (updated.q_values[i] - snapshot.q_values[i]);
+                combined.q_values_b[i] += weight * (updated.q_values_b[i] - snapshot.q_values_b[i]);
+                combined.visits[i] = combined.visits[i].saturating_add(updated.visits[i].saturating_sub(snapshot.visits[i]));
+            }
+        }
+        combined
+    }
+}
+
+/// The change an update made from `base` to `updated`, applied to `snapshot`
+/// instead, so it can be combined with other changes to `snapshot`
+pub fn rebase(snapshot: &CompactPolicy, base: &CompactPolicy, updated: &CompactPolicy) -> CompactPolicy {
+    if base == snapshot {
+        return *updated;
+    }
+    let mut rebased = *snapshot;
+    for i in 0..4 {
+        rebased.q_values[i] += updated.q_values[i] - base.q_values[i];
+        rebased.q_values_b[i] += updated.q_values_b[i] - base.q_values_b[i];
+        rebased.visits[i] = rebased.visits[i].saturating_add(updated.visits[i].saturating_sub(base.visits[i]));
+    }
+    rebased
+}
+
+/// Step size, discount and action selection used by an update
+#[derive(Debug, Clone, Copy)]
+pub struct LearningParams {
+    pub alpha: f32,
+    pub gamma: f32,
+    pub selector: ActionSelector,
+}
+
+impl LearningRule {
+    /// The policy after playing `action` for `reward` and landing in the state
+    /// whose policy is `next`.
+    ///
+    /// The next action is not known until the agent's next game, which may be
+    /// against another opponent, so `SampledSarsa` samples it from `next`.
+    /// Every rule counts the visit to `action`.
+    pub fn update<R: Rng>(
+        self,
+        policy: &CompactPolicy,
+        action: Action,
+        reward: f32,
+        next: &CompactPolicy,
+        params: LearningParams,
+        rng: &mut R,
+    ) -> CompactPolicy {
+        let next_q = match self {
+            LearningRule::QLearning => max_q(&next.q_values),
+            LearningRule::SampledSarsa => next.q_values[params.selector.choose(next, rng) as usize],
+            LearningRule::ExpectedSarsa => {
+                let probs = params.selector.probabilities(next);
+                probs.iter().zip(&next.q_values).map(|(p, q)| p * q).sum()
+            }
+            LearningRule::DoubleQ => return double_q_update(policy, action, reward, next, params, rng),
+        };
+        let q_values = policy.calculate_updated_q_values(action, reward, next_q, params.alpha, params.gamma);
+        let mut updated = CompactPolicy { q_values, ..*policy };
+        updated.visits[action as usize] = updated.visits[action as usize].saturating_add(1);
+        updated
+    }
+}
+
+fn max_q(q_values: &[f32; 4]) -> f32 {
+    q_values.iter().cloned().fold(f32::NEG_INFINITY, f32::max)
+}
+
+/// Index of the highest Q-value, ties going to the first action (JS `indexOfMax`)
+pub(crate) fn argmax(q_values: &[f32; 4]) -> usize {
+    let mut best = 0;
+    for (idx, &q) in q_values.iter().enumerate() {
+        if q > q_values[best] {
+            best = idx;
+        }
+    }
+    best
+}
+
+/// Update one of the two estimators, chosen by a coin flip.
+///
+/// `q_values` holds the mean of estimators A and B and `q_values_b` holds B,
+/// so A is recovered as `2 * q_values - q_values_b`.
+fn double_q_update<R: Rng>(
+    policy: &CompactPolicy,
+    action: Action,
+    reward: f32,
+    next: &CompactPolicy,
+    params: LearningParams,
+    rng: &mut R,
+) -> CompactPolicy {
+    let estimator_a = |p: &CompactPolicy| -> [f32; 4] { std::array::from_fn(|i| 2.0 * p.q_values[i] - p.q_values_b[i]) };
+    let (mut a, mut b) = (estimator_a(policy), policy.q_values_b);
+    let (next_a, next_b) = (estimator_a(next), next.q_values_b);
+
+    let idx = action as usize;
+    if rng.gen::<bool>() {
+        let target = reward + params.gamma * next_b[argmax(&next_a)];
+        a[idx] += params.alpha * (target - a[idx]);
+    } else {
+        let target = reward + params.gamma * next_a[argmax(&next_b)];
+        b[idx] += params.alpha * (target - b[idx]);
+    }
+
+    let mut updated = *policy;
+    updated.q_values[idx] = (a[idx] + b[idx]) / 2.0;
+    updated.q_values_b[idx] = b[idx];
+    updated.visits[idx] = updated.visits[idx].saturating_add(1);
+    updated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    fn policy(q_values: [f32; 4]) -> CompactPolicy {
+        CompactPolicy { q_values, q_values_b: q_values, visits: [0; 4] }
+    }
+
+    #[test]
+    fn test_bootstrap_targets() {
+        let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(0);
+        let current = policy([0.0; 4]);
+        let next = policy([4.0, 0.0, 0.0, 0.0]);
+        let params = LearningParams { alpha: 1.0, gamma: 1.0, selector: ActionSelector::EpsilonGreedy(0.5) };
+
+        let q = LearningRule::QLearning.update(&current, Action::Defect, 1.0, &next, params, &mut rng);
+        assert_eq!(q.q_values[Action::Defect as usize], 5.0);
+        assert_eq!(q.visits, [0, 1, 0, 0]);
+
+        // 0.5 * max + 0.5 * mean = 2 + 0.5
+        let expected = LearningRule::ExpectedSarsa.update(&current, Action::Defect, 1.0, &next, params, &mut rng);
+        assert_eq!(expected.q_values[Action::Defect as usize], 3.5);
+
+        let sarsa = LearningRule::SampledSarsa.update(&current, Action::Defect, 1.0, &next, params, &mut rng);
+        assert!([1.0, 5.0].contains(&sarsa.q_values[Action::Defect as usize]));
+    }
+
+    #[test]
+    fn test_sum_and_mean_keep_every_update() {
+        let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(0);
+        let snapshot = policy([0.0; 4]);
+        let next = policy([0.0; 4]);
+        let params = LearningParams { alpha: 0.5, gamma: 0.0, selector: ActionSelector::EpsilonGreedy(0.0) };
+        let low = LearningRule::QLearning.update(&snapshot, Action::Cooperate, 2.0, &next, params, &mut rng);
+        let high = LearningRule::QLearning.update(&snapshot, Action::Cooperate, 4.0, &next, params, &mut rng);
+        let other = LearningRule::QLearning.update(&snapshot, Action::Defect, 2.0, &next, params, &mut rng);
+        let updates = [(Action::Cooperate, low), (Action::Cooperate, high), (Action::Defect, other)];
+
+        let sum = UpdateAggregation::Sum.combine(&snapshot, &updates);
+        assert_eq!(sum.q_values, [3.0, 1.0, 0.0, 0.0]);
+        let mean = UpdateAggregation::Mean.combine(&snapshot, &updates);
+        assert_eq!(mean.q_values, [1.5, 1.0, 0.0, 0.0]);
+        assert_eq!(mean.visits, [2, 1, 0, 0]);
+    }
+
+    #[test]
+    fn test_double_q_updates_one_estimator() {
+        let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(0);
+        let current = policy([0.0; 4]);
+        let next = policy([2.0, 0.0, 0.0, 0.0]);
+        let params = LearningParams { alpha: 1.0, gamma: 1.0, selector: ActionSelector::EpsilonGreedy(0.0) };
+
+        let updated = LearningRule::DoubleQ.update(&current, Action::Cooperate, 1.0, &next, params, &mut rng);
+        let b = updated.q_values_b[0];
+        let a = 2.0 * updated.q_values[0] - b;
+        // Exactly one estimator moved to the target 1 + 2
+        assert!((a == 3.0 && b == 0.0) || (a == 0.0 && b == 3.0));
+    }
+}
@@ -1,25 +1,63 @@
 mod agent;
+mod graph;
 mod grid;
+mod hyperparams;
+mod interpret;
+mod learning;
+mod memory;
 mod video;
 mod csv_export;
+mod encounter;
+mod evolution;
+mod exploration;
+mod merge;
+mod migration;
+mod neighborhood;
+mod payoff;
+mod policy_table;
+mod reference;
+mod rng;
+mod state;
+mod strategy;
+mod topology;
 
 use clap::Parser;
 use indicatif::{ProgressBar, ProgressStyle};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tracing::{info, warn};
-use tracing_subscriber;
 
 use crate::csv_export::BufferedCsvExporter;
-use crate::grid::Grid;
+use crate::graph::{Graph, GraphSpec};
+use crate::grid::{Grid, PolicyMode, SplitFitness};
+use crate::encounter::{Encounter, RoundPayoff, Rounds};
+use crate::evolution::{Evolution, EvolutionRule};
+use crate::exploration::{Exploration, ExplorationStrategy, Schedule, ScheduleKind};
+use crate::hyperparams::{Hyperparameters, ParamDistribution};
+use crate::interpret::{PolicyReport, CLASSIFIED};
+use crate::learning::{LearningRule, UpdateAggregation};
+use crate::memory::{MemoryInheritance, MemoryRule};
+use crate::merge::{FitnessCombine, MergeInheritance, MergeRule, MergeTrigger};
+use crate::neighborhood::InteractionScope;
+use crate::payoff::{PayoffPreset, PayoffTable};
+use crate::policy_table::{PolicyEviction, PolicyTable};
+use crate::reference::{ReferenceStep, StepMode};
+use crate::state::StateEncoder;
+use crate::strategy::{StrategyLayout, StrategyMix};
+use crate::topology::{Lattice, Topology};
 #[cfg(feature = "video")]
 use crate::video::VideoEncoder;
 #[cfg(not(feature = "video"))]
 use crate::video::VideoEncoder;
 
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, disable_help_flag = true)]
 struct Args {
+    /// Print help
+    #[arg(long, action = clap::ArgAction::Help)]
+    help: Option<bool>,
+
     /// Grid width
     #[arg(short = 'w', long, default_value_t = 100)]
     width: usize,
@@ -28,7 +66,7 @@ struct Args {
     #[arg(short = 'h', long, default_value_t = 100)]
     height: usize,
     
-    /// Number of timesteps to simulate
+    /// Number of timesteps to simulate (games, in reference step mode)
     #[arg(short = 't', long, default_value_t = 1000)]
     timesteps: usize,
     
@@ -56,17 +94,238 @@ struct Args {
     #[arg(long)]
     no_video: bool,
     
-    /// Q-learning alpha parameter
-    #[arg(long, default_value_t = 0.2)]
-    alpha: f32,
+    /// Policy state: both memories packed, JS stitchmem, own memory only, or opponent memory only
+    #[arg(long, value_enum, default_value_t = StateEncoder::Packed)]
+    state_encoder: StateEncoder,
+    
+    /// Learning rule for policy updates
+    #[arg(long, value_enum, default_value_t = LearningRule::QLearning)]
+    learning_rule: LearningRule,
+    
+    /// How updates from several games to the same state in one timestep are combined
+    #[arg(long, value_enum, default_value_t = UpdateAggregation::LastWrite)]
+    update_aggregation: UpdateAggregation,
+    
+    /// Q-learning alpha per agent: VALUE, uniform:LOW:HIGH or normal:MEAN:SD
+    #[arg(long, default_value = "0.2")]
+    alpha: ParamDistribution,
+    
+    /// Q-learning gamma per agent: VALUE, uniform:LOW:HIGH or normal:MEAN:SD
+    #[arg(long, default_value = "0.95")]
+    gamma: ParamDistribution,
+    
+    /// Q-learning epsilon per agent (starting value with a schedule): VALUE, uniform:LOW:HIGH or normal:MEAN:SD
+    #[arg(long, default_value = "0.1")]
+    epsilon: ParamDistribution,
+    
+    /// Standard deviation of the noise added to alpha, gamma and epsilon when inherited on merge or split
+    #[arg(long, default_value_t = 0.0)]
+    hyperparam_mutation: f32,
+    
+    /// Action selection: epsilon-greedy, Boltzmann softmax, or UCB on visit counts
+    #[arg(long, value_enum, default_value_t = ExplorationStrategy::EpsilonGreedy)]
+    exploration: ExplorationStrategy,
+    
+    /// Boltzmann temperature (starting value with a schedule)
+    #[arg(long, default_value_t = 1.0)]
+    temperature: f32,
+    
+    /// UCB exploration constant (starting value with a schedule)
+    #[arg(long, default_value_t = 1.0)]
+    ucb_c: f32,
+    
+    /// How epsilon, temperature or the UCB constant changes over timesteps
+    #[arg(long, value_enum, default_value_t = ScheduleKind::Constant)]
+    exploration_schedule: ScheduleKind,
+    
+    /// Value the exploration schedule anneals towards
+    #[arg(long, default_value_t = 0.0)]
+    exploration_end: f32,
+    
+    /// Timesteps to reach the end value (linear), time constant (exponential), or step length (step)
+    #[arg(long, default_value_t = 1000)]
+    exploration_horizon: u64,
+    
+    /// Factor applied once per horizon by the step schedule
+    #[arg(long, default_value_t = 0.5)]
+    exploration_step_factor: f32,
+    
+    /// JSON payoff file: 16-entry array, JS `payofftable` object, or T/R/P/S object
+    #[arg(long, conflicts_with_all = ["payoff_matrix", "payoff_preset", "payoff_t", "payoff_r", "payoff_p", "payoff_s", "payoff_merge", "payoff_split"])]
+    payoff_file: Option<PathBuf>,
+    
+    /// Full payoff matrix: 16 comma-separated entries, rows and columns in C,D,M,S order
+    #[arg(long, value_delimiter = ',', allow_negative_numbers = true, conflicts_with_all = ["payoff_preset", "payoff_t", "payoff_r", "payoff_p", "payoff_s", "payoff_merge", "payoff_split"])]
+    payoff_matrix: Option<Vec<f32>>,
+    
+    /// Named T/R/P/S payoffs; the single-value flags below override it
+    #[arg(long, value_enum, default_value_t = PayoffPreset::Js)]
+    payoff_preset: PayoffPreset,
+    
+    /// Temptation payoff T (Defect against Cooperate)
+    #[arg(long, allow_negative_numbers = true)]
+    payoff_t: Option<f32>,
+    
+    /// Reward payoff R (mutual cooperation)
+    #[arg(long, allow_negative_numbers = true)]
+    payoff_r: Option<f32>,
+    
+    /// Punishment payoff P (mutual defection)
+    #[arg(long, allow_negative_numbers = true)]
+    payoff_p: Option<f32>,
+    
+    /// Sucker payoff S (Cooperate against Defect)
+    #[arg(long, allow_negative_numbers = true)]
+    payoff_s: Option<f32>,
+    
+    /// Payoff to each player when both choose Merge
+    #[arg(long, allow_negative_numbers = true)]
+    payoff_merge: Option<f32>,
+    
+    /// Payoff to both players in any game where someone chooses Split
+    #[arg(long, allow_negative_numbers = true)]
+    payoff_split: Option<f32>,
+    
+    /// Rounds per pairing: a fixed count N, or geometric:W to continue after each round with probability W
+    #[arg(long, default_value = "1")]
+    rounds: Rounds,
+    
+    /// Fitness from a multi-round encounter: the sum or the mean of its round payoffs
+    #[arg(long, value_enum, default_value_t = RoundPayoff::Accumulate)]
+    round_payoff: RoundPayoff,
+    
+    /// Initial strategy weights, e.g. learner=0.8,tft=0.1,all-d=0.1
+    /// (learner, tft, grim, wsls, all-c, all-d, random, merge-happy)
+    #[arg(long, default_value = "learner=1")]
+    strategy_mix: StrategyMix,
+    
+    /// Placement of initial strategies: per cell, or in square patches
+    #[arg(long, value_enum, default_value_t = StrategyLayout::Random)]
+    strategy_layout: StrategyLayout,
+    
+    /// Side length of strategy patches, in cells
+    #[arg(long, default_value_t = 10)]
+    patch_size: usize,
+    
+    /// Policy ownership: one shared Q-table or one per agent
+    #[arg(long, value_enum, default_value_t = PolicyMode::Shared)]
+    policy_mode: PolicyMode,
+    
+    /// Most policy states to keep between timesteps (unbounded if unset)
+    #[arg(long)]
+    policy_capacity: Option<usize>,
+    
+    /// Which states a bounded policy table evicts first
+    #[arg(long, value_enum, default_value_t = PolicyEviction::Lru)]
+    policy_eviction: PolicyEviction,
+    
+    /// Start from the policies saved in this file (.json, or binary otherwise)
+    #[arg(long)]
+    load_policies: Option<PathBuf>,
+    
+    /// Play from the policy table without updating it
+    #[arg(long)]
+    freeze_policies: bool,
+    
+    /// Save the policy table here at the end of the run (.json, or binary otherwise)
+    #[arg(long)]
+    save_policies: Option<PathBuf>,
+    
+    /// Also save the policy table every N timesteps, to the save path with the timestep appended
+    #[arg(long, requires = "save_policies")]
+    save_policies_every: Option<usize>,
+    
+    /// Write a CSV report of how the learned greedy actions compare with classic strategies
+    #[arg(long)]
+    policy_report: Option<PathBuf>,
+    
+    /// Which joint actions merge two organisms
+    #[arg(long, value_enum, default_value_t = MergeTrigger::Both)]
+    merge_trigger: MergeTrigger,
+    
+    /// Success probability of each Merge action with the probabilistic trigger
+    #[arg(long, default_value_t = 0.5)]
+    merge_probability: f32,
+    
+    /// How parent fitness combines into the merged organism's fitness
+    #[arg(long, value_enum, default_value_t = FitnessCombine::Sum)]
+    merge_fitness: FitnessCombine,
+    
+    /// Which parent a merged organism inherits memory and policy from
+    #[arg(long, value_enum, default_value_t = MergeInheritance::Fitter)]
+    merge_inheritance: MergeInheritance,
+    
+    /// Fitness of split parents: inherit the organism's fitness, or half each
+    #[arg(long, value_enum, default_value_t = SplitFitness::Inherit)]
+    split_fitness: SplitFitness,
+    
+    /// Longest memory an agent can have (JS MAX_MEM); initial lengths are drawn from 1..=max
+    #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(u8).range(1..=agent::MAX_MEM_LENGTH as i64))]
+    max_mem: u8,
+    
+    /// Memory length of a merged organism: the inheriting parent's, the longer, the shorter, or the fitter parent's
+    #[arg(long, value_enum, default_value_t = MemoryInheritance::Inherited)]
+    memory_inheritance: MemoryInheritance,
+    
+    /// Probability that a split parent's memory length grows or shrinks by one
+    #[arg(long, default_value_t = 0.0)]
+    memory_mutation: f32,
+    
+    /// Cell lattice: square with Moore or von Neumann neighborhoods, or hexagonal
+    #[arg(long, value_enum, default_value_t = Lattice::Moore)]
+    lattice: Lattice,
+    
+    /// Neighborhood radius in lattice steps
+    #[arg(long, default_value_t = 1)]
+    radius: usize,
+    
+    /// Wrap the grid edges around into a torus
+    #[arg(long)]
+    wrap: bool,
+    
+    /// Play on a generated graph instead of the grid: watts-strogatz:N:K:P, barabasi-albert:N:M or random-regular:N:D
+    #[arg(long, conflicts_with = "graph_file")]
+    graph: Option<GraphSpec>,
+    
+    /// Play on the graph in this edge-list file (one `u v` pair of 0-based node ids per line)
+    #[arg(long)]
+    graph_file: Option<PathBuf>,
+    
+    /// Fraction of cells left empty at the start
+    #[arg(long, default_value_t = 0.0)]
+    vacancy: f64,
+    
+    /// Chance per timestep that a single-cell agent moves to an empty neighboring cell (parallel step mode)
+    #[arg(long, default_value_t = 0.0)]
+    migration: f32,
+    
+    /// Evolutionary update between learning lifetimes (none if unset)
+    #[arg(long, value_enum)]
+    evolution: Option<EvolutionRule>,
+    
+    /// Selection strength of the evolutionary update
+    #[arg(long, default_value_t = 1.0)]
+    selection_strength: f32,
+    
+    /// Timesteps between evolutionary updates (games in the reference step mode)
+    #[arg(long, default_value_t = 100, value_parser = clap::value_parser!(u64).range(1..))]
+    evolution_period: u64,
     
-    /// Q-learning gamma parameter
-    #[arg(long, default_value_t = 0.95)]
-    gamma: f32,
+    /// Opponent selection: per grid cell, or per organism from its boundary
+    #[arg(long, value_enum, default_value_t = InteractionScope::Cell)]
+    interaction_scope: InteractionScope,
     
-    /// Q-learning epsilon parameter
-    #[arg(long, default_value_t = 0.1)]
-    epsilon: f32,
+    /// Games each organism starts per timestep with the organism scope
+    #[arg(long, default_value_t = 1)]
+    games_per_organism: usize,
+    
+    /// Stepping: parallel pipeline, or the sequential algorithm.md loop
+    #[arg(long, value_enum, default_value_t = StepMode::Parallel)]
+    step_mode: StepMode,
+    
+    /// Random seed; runs with the same seed are identical for any thread count
+    #[arg(long)]
+    seed: Option<u64>,
     
     /// Number of threads (0 = auto)
     #[arg(long, default_value_t = 0)]
@@ -77,6 +336,43 @@ struct Args {
     print_pass_stats: bool,
 }
 
+/// Payoff table from the file, the full matrix, or the preset plus overrides
+fn payoff_table(args: &Args) -> Result<PayoffTable, Box<dyn std::error::Error>> {
+    if let Some(path) = &args.payoff_file {
+        return PayoffTable::from_file(path);
+    }
+    if let Some(entries) = &args.payoff_matrix {
+        return PayoffTable::from_entries(entries);
+    }
+    
+    let mut payoffs = args.payoff_preset.payoffs();
+    for (value, field) in [
+        (args.payoff_t, &mut payoffs.t),
+        (args.payoff_r, &mut payoffs.r),
+        (args.payoff_p, &mut payoffs.p),
+        (args.payoff_s, &mut payoffs.s),
+        (args.payoff_merge, &mut payoffs.merge),
+        (args.payoff_split, &mut payoffs.split),
+    ] {
+        if let Some(value) = value {
+            *field = value;
+        }
+    }
+    let table = PayoffTable::from_dilemma(payoffs);
+    table.validate()?;
+    Ok(table)
+}
+
+/// `path` with `_t<timestep>` added before the extension
+fn timestep_path(path: &Path, timestep: usize) -> PathBuf {
+    let stem = path.file_stem().map(|stem| stem.to_string_lossy()).unwrap_or_default();
+    let name = match path.extension() {
+        Some(ext) => format!("{}_t{}.{}", stem, timestep, ext.to_string_lossy()),
+        None => format!("{}_t{}", stem, timestep),
+    };
+    path.with_file_name(name)
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Initialize logging
     tracing_subscriber::fmt::init();
@@ -92,17 +388,141 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
     
     info!("IPD Simulator - High Performance Edition");
-    info!("Grid size: {}x{} ({} agents)", args.width, args.height, args.width * args.height);
+    
+    // Graph files fix the cell count; generated graphs are drawn from the run seed
+    let graph_file = args.graph_file.as_deref().map(Graph::from_file).transpose()?;
+    let (width, height) = match (&graph_file, &args.graph) {
+        (Some(graph), _) => (graph.node_count(), 1),
+        (None, Some(spec)) => (spec.node_count(), 1),
+        (None, None) => (args.width, args.height),
+    };
+    info!("Grid size: {}x{} ({} agents)", width, height, width * height);
     info!("Timesteps: {}", args.timesteps);
     
+    let payoffs = payoff_table(&args)?;
+    info!("Payoff matrix (row player's payoff):\n{}", payoffs);
+    if !payoffs.is_prisoners_dilemma() {
+        warn!("C/D payoffs do not satisfy T > R > P > S and 2R > T + S; this is not a Prisoner's Dilemma");
+    }
+    
     // Initialize grid
-    let mut grid = Grid::new(args.width, args.height);
-    grid.alpha = args.alpha;
-    grid.gamma = args.gamma;
-    grid.epsilon = args.epsilon;
+    let mut grid = match args.seed {
+        Some(seed) => Grid::with_seed(width, height, seed),
+        None => Grid::new(width, height),
+    };
+    info!("Seed: {}", grid.seed);
+    grid.learning_rule = args.learning_rule;
+    grid.update_aggregation = args.update_aggregation;
+    grid.set_hyperparameters(Hyperparameters {
+        alpha: args.alpha,
+        gamma: args.gamma,
+        epsilon: args.epsilon,
+        mutation: args.hyperparam_mutation,
+    });
+    info!(
+        "Hyperparameters: alpha {} | gamma {} | epsilon {} | mutation {}",
+        args.alpha, args.gamma, args.epsilon, args.hyperparam_mutation
+    );
+    grid.exploration = Exploration {
+        strategy: args.exploration,
+        schedule: Schedule {
+            kind: args.exploration_schedule,
+            end: args.exploration_end,
+            horizon: args.exploration_horizon,
+            step_factor: args.exploration_step_factor,
+        },
+        temperature: args.temperature,
+        ucb_c: args.ucb_c,
+    };
+    grid.policy_mode = args.policy_mode;
+    if let Some(capacity) = args.policy_capacity {
+        grid.policy_table = PolicyTable::bounded(capacity, args.policy_eviction, grid.seed);
+        info!("Policy table: at most {} states, {:?} eviction", capacity, args.policy_eviction);
+    }
+    if let Some(path) = &args.load_policies {
+        let loaded = grid.policy_table.load(path)?;
+        info!("Loaded {} policies from {}", loaded, path.display());
+    }
+    grid.freeze_policies = args.freeze_policies;
+    if args.freeze_policies {
+        info!("Policies are frozen: no learning");
+    }
+    grid.set_strategies(&args.strategy_mix, args.strategy_layout, args.patch_size);
+    info!("Strategies: {} ({:?}, patch size {})", args.strategy_mix, args.strategy_layout, args.patch_size);
+    grid.state_encoder = args.state_encoder;
+    grid.payoff_table = payoffs;
+    grid.encounter = Encounter { rounds: args.rounds, payoff: args.round_payoff };
+    info!("Encounter: {:?}", grid.encounter);
+    grid.merge_rule = MergeRule {
+        trigger: args.merge_trigger,
+        merge_probability: args.merge_probability,
+        fitness: args.merge_fitness,
+        inheritance: args.merge_inheritance,
+    };
+    grid.split_fitness = args.split_fitness;
+    info!("Merge rule: {:?}", grid.merge_rule);
+    grid.set_memory_rule(MemoryRule {
+        inheritance: args.memory_inheritance,
+        mutation: args.memory_mutation,
+        max_length: args.max_mem,
+    });
+    info!("Memory rule: {:?}", grid.memory_rule);
+    let topology = match (graph_file, &args.graph) {
+        (Some(graph), _) => Topology::Graph(Arc::new(graph)),
+        (None, Some(spec)) => Topology::Graph(Arc::new(spec.build(grid.seed)?)),
+        (None, None) => Topology::Lattice { lattice: args.lattice, radius: args.radius, wrap: args.wrap },
+    };
+    topology.validate(height)?;
+    match &topology {
+        Topology::Graph(graph) => info!("Topology: graph with {} nodes and {} edges", graph.node_count(), graph.edge_count()),
+        lattice => info!("Topology: {:?}", lattice),
+    }
+    grid.set_topology(topology);
+    if !(0.0..1.0).contains(&args.vacancy) {
+        return Err(format!("vacancy density must be in [0, 1), got {}", args.vacancy).into());
+    }
+    grid.set_vacancies(args.vacancy);
+    grid.migration_rate = args.migration;
+    info!("Vacancy: {} ({} cells occupied), migration rate {}", args.vacancy, grid.active_mask.count_ones(), args.migration);
+    grid.evolution = args.evolution.map(|rule| Evolution {
+        rule,
+        selection: args.selection_strength,
+        period: args.evolution_period,
+    });
+    if let Some(evolution) = &grid.evolution {
+        info!("Evolution: {:?}", evolution);
+    }
+    let single_round = args.rounds == Rounds::Fixed(1);
+    if (args.migration > 0.0 || !single_round) && args.step_mode == StepMode::Reference {
+        warn!("Migration and multi-round encounters only run in the parallel step mode");
+    }
+    let reference_merges = MergeRule::reference();
+    let custom_merges = args.merge_trigger != reference_merges.trigger
+        || args.merge_fitness != reference_merges.fitness
+        || args.merge_inheritance != reference_merges.inheritance
+        || args.split_fitness != SplitFitness::Inherit;
+    if custom_merges && args.step_mode == StepMode::Reference {
+        warn!(
+            "The reference step mode always merges like JS (either player, mean fitness, fitter parent) and \
+             splits with inherited fitness; --merge-trigger, --merge-fitness, --merge-inheritance and \
+             --split-fitness only apply to the parallel step mode"
+        );
+    }
+    grid.set_interaction_scope(args.interaction_scope);
+    grid.games_per_organism = args.games_per_organism;
+    info!("Interaction scope: {:?}", args.interaction_scope);
+    info!("Policy mode: {:?}", args.policy_mode);
+    info!("State encoder: {:?}", args.state_encoder);
+    info!("Learning rule: {:?}", args.learning_rule);
+    info!("Update aggregation: {:?}", args.update_aggregation);
+    info!("Exploration: {:?}", grid.exploration);
+    info!("Step mode: {:?}", args.step_mode);
     
     // Initialize video encoder
-    let mut video_encoder = if !args.no_video {
+    if !args.no_video && !grid.topology.is_lattice() {
+        warn!("Graph topologies have no grid layout to draw; continuing without video.");
+    }
+    let mut video_encoder = if !args.no_video && grid.topology.is_lattice() {
         match VideoEncoder::new(
             &args.output_video,
             args.video_width,
@@ -134,12 +554,26 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut total_sim_time = Duration::ZERO;
     let mut total_stats_time = Duration::ZERO;
     let mut total_export_time = Duration::ZERO;
+    let mut completed_timesteps = 0;
     
     // Main simulation loop
     for timestep in 0..args.timesteps {
         // Simulation step
         let sim_start = Instant::now();
-        grid.step();
+        match args.step_mode {
+            StepMode::Parallel => grid.step(),
+            StepMode::Reference => match grid.step_reference() {
+                ReferenceStep::Continue => {}
+                ReferenceStep::SingleOrganism => {
+                    info!("Single organism covers the grid at timestep {}", timestep);
+                    break;
+                }
+                ReferenceStep::Empty => {
+                    info!("No cell is occupied at timestep {}", timestep);
+                    break;
+                }
+            },
+        }
         total_sim_time += sim_start.elapsed();
         
         // Calculate statistics
@@ -161,6 +595,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         
         total_export_time += export_start.elapsed();
         
+        completed_timesteps = timestep + 1;
+        
         // Update progress
         progress.set_position(timestep as u64 + 1);
         progress.set_message(format!(
@@ -192,11 +628,25 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 stats.pass_stats.state_update_time,
                 stats.pass_stats.deferred_op_time
             );
+            info!(
+                "Agent Arena: Slots: {} | Live: {} | Peak: {} | Reclaimed: {} | Memory: {:.1} MB",
+                stats.pass_stats.agent_slots,
+                stats.pass_stats.live_agents,
+                stats.pass_stats.peak_live_agents,
+                stats.pass_stats.reclaimed_agents,
+                stats.pass_stats.agent_memory_bytes as f64 / (1024.0 * 1024.0)
+            );
+        }
+
+        if let (Some(path), Some(every)) = (&args.save_policies, args.save_policies_every) {
+            if every > 0 && (timestep + 1) % every == 0 {
+                grid.policy_table.save(&timestep_path(path, timestep + 1))?;
+            }
         }
 
         if args.print_pass_stats {
             println!(
-                "{},{},{},{},{},{},{},{}",
+                "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
                 timestep,
                 stats.pass_stats.num_interactions,
                 stats.pass_stats.num_updates,
@@ -204,7 +654,21 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 stats.pass_stats.interaction_generation_time,
                 stats.pass_stats.interaction_processing_time,
                 stats.pass_stats.state_update_time,
-                stats.pass_stats.deferred_op_time
+                stats.pass_stats.deferred_op_time,
+                stats.pass_stats.migration_time,
+                stats.pass_stats.evolution_time,
+                stats.pass_stats.agent_slots,
+                stats.pass_stats.live_agents,
+                stats.pass_stats.peak_live_agents,
+                stats.pass_stats.reclaimed_agents,
+                stats.pass_stats.agent_memory_bytes,
+                stats.pass_stats.policy_states,
+                stats.pass_stats.policy_inserts,
+                stats.pass_stats.policy_evictions,
+                stats.pass_stats.policy_memory_bytes,
+                stats.pass_stats.shared_state_updates,
+                stats.pass_stats.migrations,
+                stats.pass_stats.replacements
             );
         }
     }
@@ -219,6 +683,30 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     
     csv_exporter.finish()?;
     
+    if let Some(path) = &args.save_policies {
+        grid.policy_table.save(path)?;
+        info!("Saved {} policies to {}", grid.policy_table.len(), path.display());
+    }
+    if let Some(path) = &args.policy_report {
+        let report = PolicyReport::analyze(&grid.policy_table, grid.state_encoder);
+        report.write_csv(path)?;
+        let total = report.total();
+        let agreement: Vec<String> = CLASSIFIED
+            .iter()
+            .zip(&total.agree)
+            .map(|(strategy, &agree)| format!("{} {:.1}%", strategy.name(), 100.0 * total.fraction(agree)))
+            .collect();
+        info!(
+            "Learned policies ({} states, {} never updated): {} | merge {:.1}% | split {:.1}%",
+            total.states,
+            report.unvisited,
+            agreement.join(", "),
+            100.0 * total.fraction(total.merge),
+            100.0 * total.fraction(total.split)
+        );
+        info!("Policy report written to {}", path.display());
+    }
+    
     // Print performance summary
     let total_time = total_sim_time + total_stats_time + total_export_time;
     println!("\n=== Performance Summary ===");
@@ -235,8 +723,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         total_export_time.as_secs_f64(),
         (total_export_time.as_secs_f64() / total_time.as_secs_f64()) * 100.0
     );
-    println!("Average FPS: {:.2}", args.timesteps as f64 / total_time.as_secs_f64());
-    println!("Agents processed: {}", args.width * args.height);
+    println!("Average FPS: {:.2}", completed_timesteps as f64 / total_time.as_secs_f64());
+    println!("Agents processed: {}", grid.grid_width * grid.grid_height);
     
     Ok(())
 }
@@ -244,6 +732,25 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::agent::{Action, DeferredOp, INITIAL_FITNESS};
+    use crate::grid::JointActionCounts;
+    use crate::strategy::Strategy;
+    
+    /// Queue a summed-fitness merge of `agent1` and `agent2` that inherits
+    /// everything from `inherit_from`, commit it with one step and return
+    /// the merged organism's root
+    fn force_merge(grid: &mut Grid, agent1: usize, agent2: usize, inherit_from: usize) -> usize {
+        grid.deferred_ops.push(DeferredOp::Merge {
+            agent1: agent1 as u32,
+            agent2: agent2 as u32,
+            fitness: FitnessCombine::Sum,
+            inherit_from: inherit_from as u32,
+            policy_from: inherit_from as u32,
+            fitter: inherit_from as u32,
+        });
+        grid.step();
+        grid.find_root(agent1)
+    }
     
     #[test]
     fn test_small_grid() {
@@ -257,6 +764,502 @@ mod tests {
         }
     }
     
+    #[test]
+    fn test_per_agent_policies() {
+        let mut grid = Grid::with_seed(4, 4, 3);
+        grid.policy_mode = PolicyMode::PerAgent;
+        
+        let key_a = grid.policy_key(&grid.agents[0], 42);
+        let key_b = grid.policy_key(&grid.agents[1], 42);
+        assert_ne!(key_a, key_b);
+        
+        // The organism plays with the fitter parent's table
+        grid.agents[0].fitness = 3.0;
+        grid.agents[1].fitness = 5.0;
+        let mut rng = rng::stream(grid.seed, 0, rng::DOMAIN_GAME, 0);
+        let (fitter, _) = grid.merge_rule.pick_parents((0, 3.0), (1, 5.0), &mut rng);
+        assert_eq!(fitter, 1);
+        let merged = force_merge(&mut grid, 0, 1, fitter as usize);
+        assert_eq!(grid.agents[merged].policy_owner, grid.agents[1].policy_owner);
+        assert_eq!(grid.policy_key(&grid.agents[merged], 42), key_b);
+        
+        // Both parents get the organism's table back on split
+        assert!(grid.split_agent(merged, SplitFitness::Inherit));
+        for parent in [0, 1] {
+            assert_eq!(grid.agents[parent].policy_owner, 1);
+            assert_eq!(grid.policy_key(&grid.agents[parent], 42), key_b);
+        }
+    }
+    
+    #[test]
+    fn test_learning_exploration_and_state_options_run() {
+        let strategies = [ExplorationStrategy::EpsilonGreedy, ExplorationStrategy::Boltzmann, ExplorationStrategy::Ucb];
+        let rules = [LearningRule::QLearning, LearningRule::SampledSarsa, LearningRule::ExpectedSarsa, LearningRule::DoubleQ];
+        let encoders = [StateEncoder::Packed, StateEncoder::Stitch, StateEncoder::Own, StateEncoder::Opponent];
+        for ((rule, strategy), encoder) in rules.into_iter().zip(strategies.into_iter().cycle()).zip(encoders) {
+            let mut grid = Grid::with_seed(8, 8, 9);
+            grid.learning_rule = rule;
+            grid.state_encoder = encoder;
+            grid.exploration.strategy = strategy;
+            grid.exploration.schedule.kind = ScheduleKind::Exponential;
+            grid.exploration.schedule.horizon = 5;
+            for _ in 0..10 {
+                grid.step();
+                grid.step_reference();
+            }
+            
+            let agent = &grid.agents[0];
+            let state = grid.state_encoder.encode(agent, &grid.agents[1]);
+            let policy = grid.policy_table.get_or_create(grid.policy_key(agent, state));
+            assert!(policy.q_values.iter().chain(&policy.q_values_b).all(|q| q.is_finite()));
+        }
+    }
+    
+    #[test]
+    fn test_reference_step_stops_on_one_organism() {
+        let mut grid = Grid::with_seed(2, 1, 2);
+        force_merge(&mut grid, 0, 1, 0);
+        assert_eq!(grid.step_reference(), ReferenceStep::SingleOrganism);
+    }
+    
+    #[test]
+    fn test_reference_steps() {
+        let mut grid = Grid::with_seed(6, 6, 7);
+        
+        for _ in 0..500 {
+            if grid.step_reference() != ReferenceStep::Continue {
+                break;
+            }
+            
+            // Every cell's cached root is a live organism containing it
+            for cell in grid.active_mask.iter_ones() {
+                let root = grid.find_root(cell);
+                assert_eq!(grid.root_cache[cell] as usize, root);
+                assert_eq!(grid.organism_size(cell) as usize, grid.member_cells(root).len());
+            }
+        }
+    }
+    
+    #[test]
+    fn test_organism_scope_plays_fixed_games_per_organism() {
+        let mut grid = Grid::with_seed(12, 12, 5);
+        grid.merge_rule.trigger = MergeTrigger::Either;
+        grid.set_interaction_scope(InteractionScope::Organism);
+        grid.games_per_organism = 2;
+        
+        for _ in 0..10 {
+            let organisms = grid.active_roots().len();
+            grid.step();
+            assert!(grid.pass_stats.num_interactions <= 2 * organisms);
+        }
+        
+        // Every organism's boundary lies outside it and touches it
+        let index = grid.neighborhoods.as_ref().unwrap();
+        for root in grid.active_roots() {
+            let members = grid.member_cells(root);
+            for &cell in index.boundary(root) {
+                assert!(!members.contains(&(cell as usize)));
+            }
+        }
+    }
+    
+    #[test]
+    fn test_split_restores_parents() {
+        let mut grid = Grid::with_seed(4, 4, 1);
+        grid.agents[0].fitness = 3.0;
+        grid.agents[1].fitness = 5.0;
+        let merged = force_merge(&mut grid, 0, 1, 1);
+        assert_eq!(merged, grid.find_root(1));
+        assert!(grid.agents[merged].is_multicellular());
+        assert_eq!(grid.organism_size(0) as usize, grid.member_cells(merged).len());
+        
+        let (parent1, parent2) = (grid.agents[merged].parent_1 as usize, grid.agents[merged].parent_2 as usize);
+        let fitness = grid.agents[merged].fitness;
+        assert!(grid.split_agent(merged, SplitFitness::Halve));
+        for parent in [parent1, parent2] {
+            assert_eq!(grid.agents[parent].child, u32::MAX);
+            assert_eq!(grid.agents[parent].fitness, fitness / 2.0);
+            assert_eq!(grid.agents[parent].policy_owner, grid.agents[merged].policy_owner);
+        }
+        assert!(!grid.agents[merged].is_multicellular());
+        assert!(!grid.split_agent(merged, SplitFitness::Inherit));
+        
+        // The retired slot is reused by the next merge instead of growing the arena
+        let slots = grid.agents.len();
+        force_merge(&mut grid, parent1, parent2, parent1);
+        assert_eq!(grid.pass_stats.reclaimed_agents, 1);
+        assert!(grid.agents.len() < slots + grid.pass_stats.num_interactions);
+        assert_eq!(grid.pass_stats.live_agents, grid.agents.len() - grid.free_agents.len());
+        assert_eq!(grid.organism_size(0) as usize, grid.member_cells(grid.find_root(0)).len());
+    }
+    
+    #[test]
+    fn test_stress_tracks_joint_actions() {
+        let mut games = JointActionCounts::default();
+        games.record(Action::Cooperate, Action::Merge);
+        games.record(Action::Split, Action::Merge);
+        games.record(Action::Defect, Action::Cooperate);
+        games.record(Action::Defect, Action::Split);
+        assert_eq!(games.get(Action::Merge, Action::Split), 1);
+        assert_eq!(games.stress(), 1);
+        
+        let mut grid = Grid::with_seed(10, 10, 3);
+        let mut stress = 0;
+        for _ in 0..10 {
+            grid.step();
+            let counts = grid.pass_stats.joint_actions;
+            let total: u32 = JointActionCounts::PAIRS.iter().map(|&(a, b)| counts.get(a, b)).sum();
+            assert_eq!(total as usize, grid.pass_stats.num_interactions);
+            stress += counts.stress();
+        }
+        assert_eq!(grid.get_statistics().stress, stress);
+    }
+    
+    #[test]
+    fn test_hyperparameters_are_drawn_and_inherited() {
+        let mut grid = Grid::with_seed(6, 6, 2);
+        grid.set_hyperparameters(Hyperparameters {
+            alpha: "uniform:0.1:0.5".parse().unwrap(),
+            ..Hyperparameters::default()
+        });
+        assert!(grid.agents.iter().all(|agent| (0.1..0.5).contains(&agent.alpha) && agent.gamma == 0.95));
+        assert_ne!(grid.agents[0].alpha, grid.agents[1].alpha);
+        
+        let merged = force_merge(&mut grid, 0, 1, 1);
+        assert_eq!(grid.agents[merged].alpha, grid.agents[1].alpha);
+        
+        let stats = grid.get_statistics();
+        assert!(stats.alpha.min >= 0.1 && stats.alpha.max < 0.5 && stats.alpha.std > 0.0);
+        assert_eq!(stats.gamma.std, 0.0);
+        
+        // Split parents take over the organism's values, mutated
+        grid.hyperparams.mutation = 0.1;
+        let alpha = grid.agents[merged].alpha;
+        let parent = grid.agents[merged].parent_1 as usize;
+        assert!(grid.split_agent(merged, SplitFitness::Inherit));
+        assert_ne!(grid.agents[parent].alpha, alpha);
+    }
+    
+    #[test]
+    fn test_memory_length_evolves() {
+        let mut grid = Grid::with_seed(6, 6, 4);
+        grid.set_memory_rule(MemoryRule { inheritance: MemoryInheritance::Max, mutation: 1.0, max_length: 3 });
+        assert!(grid.agents.iter().all(|agent| (1..=3).contains(&agent.mem_length)));
+        
+        let longest = grid.agents[0].mem_length.max(grid.agents[1].mem_length);
+        let merged = force_merge(&mut grid, 0, 1, 0);
+        assert_eq!(grid.agents[merged].mem_length, longest);
+        
+        let stats = grid.get_statistics();
+        assert_eq!(stats.mem_lengths.iter().sum::<usize>(), stats.organisms);
+        assert_eq!(stats.mem_lengths[4..].iter().sum::<usize>(), 0);
+        
+        // Mutation always moves split parents one step, within 1..=3
+        let parents = [grid.agents[merged].parent_1, grid.agents[merged].parent_2];
+        assert!(grid.split_agent(merged, SplitFitness::Inherit));
+        for parent in parents {
+            let length = grid.agents[parent as usize].mem_length;
+            assert!((1..=3).contains(&length) && length.abs_diff(longest) <= 1);
+        }
+    }
+    
+    #[test]
+    fn test_fitter_memory_is_judged_before_the_game() {
+        let mut grid = Grid::with_seed(2, 1, 5);
+        grid.merge_rule = MergeRule::reference();
+        grid.set_memory_rule(MemoryRule { inheritance: MemoryInheritance::Fitter, mutation: 0.0, max_length: 5 });
+        grid.agents[0].strategy = Strategy::AllD;
+        grid.agents[1].strategy = Strategy::MergeHappy;
+        (grid.agents[0].mem_length, grid.agents[0].fitness) = (2, 1.0);
+        (grid.agents[1].mem_length, grid.agents[1].fitness) = (4, 2.0);
+        grid.step();
+        
+        // Defecting on the merge offer makes cell 0 the fitter one afterwards
+        assert!(grid.agents[0].fitness > grid.agents[1].fitness);
+        let merged = grid.find_root(0);
+        assert_ne!(merged, 0);
+        assert_eq!(grid.agents[merged].mem_length, 4);
+    }
+    
+    #[test]
+    fn test_update_aggregation_keeps_every_game() {
+        // Two identical cells play each other twice, so all four updates hit one state
+        let visits_after_step = |aggregation: UpdateAggregation| {
+            let mut grid = Grid::with_seed(2, 1, 8);
+            grid.update_aggregation = aggregation;
+            grid.agents[1].mem_length = grid.agents[0].mem_length;
+            let key = grid.policy_key(&grid.agents[0], grid.state_encoder.encode(&grid.agents[0], &grid.agents[1]));
+            let before: u32 = grid.policy_table.get_or_create(key).visits.iter().sum();
+            grid.step();
+            assert_eq!(grid.pass_stats.shared_state_updates, 3);
+            let after: u32 = grid.policy_table.get_or_create(key).visits.iter().sum();
+            after - before
+        };
+        
+        assert_eq!(visits_after_step(UpdateAggregation::LastWrite), 1);
+        for aggregation in [UpdateAggregation::Ordered, UpdateAggregation::Sum, UpdateAggregation::Mean] {
+            assert_eq!(visits_after_step(aggregation), 4);
+        }
+    }
+    
+    #[test]
+    fn test_bounded_policy_table() {
+        let mut grid = Grid::with_seed(10, 10, 6);
+        grid.policy_mode = PolicyMode::PerAgent;
+        grid.policy_table = PolicyTable::bounded(150, PolicyEviction::LeastVisited, grid.seed);
+        let mut evictions = 0;
+        for _ in 0..10 {
+            grid.step();
+            assert!(grid.pass_stats.policy_states <= 150);
+            assert!(grid.pass_stats.policy_memory_bytes > 0);
+            evictions += grid.pass_stats.policy_evictions;
+        }
+        assert!(evictions > 0);
+        assert_eq!(grid.policy_table.len(), grid.pass_stats.policy_states);
+    }
+    
+    #[test]
+    fn test_warm_start_from_frozen_policies() {
+        let path = std::env::temp_dir().join("ipd_warm_start_test.bin");
+        assert_eq!(timestep_path(&path, 40).file_name().unwrap(), "ipd_warm_start_test_t40.bin");
+        
+        let mut trained = Grid::with_seed(8, 8, 9);
+        for _ in 0..5 {
+            trained.step();
+        }
+        trained.policy_table.save(&path).unwrap();
+        let saved = trained.policy_table.entries();
+        
+        let mut grid = Grid::with_seed(8, 8, 10);
+        assert_eq!(grid.policy_table.load(&path).unwrap(), saved.len());
+        std::fs::remove_file(&path).unwrap();
+        grid.freeze_policies = true;
+        for _ in 0..5 {
+            grid.step();
+            grid.step_reference();
+        }
+        // Unseen states are played without being stored
+        assert_eq!(grid.policy_table.len(), saved.len());
+        for (key, policy) in saved {
+            assert_eq!(grid.policy_table.get_or_create(key), policy);
+        }
+    }
+    
+    #[test]
+    fn test_vacant_cells_never_play() {
+        for scope in [InteractionScope::Cell, InteractionScope::Organism] {
+            let mut grid = Grid::with_seed(12, 12, 6);
+            grid.merge_rule = MergeRule::reference();
+            grid.set_vacancies(0.3);
+            grid.set_interaction_scope(scope);
+            grid.migration_rate = 0.5;
+            let occupied = grid.active_mask.count_ones();
+
+            let mut migrations = 0;
+            for _ in 0..20 {
+                grid.step();
+                migrations += grid.pass_stats.migrations;
+                assert_eq!(grid.active_mask.count_ones(), occupied);
+            }
+            assert!(migrations > 0);
+            assert!(grid.get_statistics().multicellular_agents > 0);
+
+            // A vacant cell is its own root and never joined an organism
+            for cell in grid.active_mask.iter_zeros().filter(|&cell| cell < 144) {
+                assert_eq!(grid.root_cache[cell] as usize, cell);
+                assert_eq!(grid.agents[cell].child, u32::MAX, "{:?}", scope);
+            }
+        }
+    }
+
+    #[test]
+    fn test_multi_round_encounters() {
+        let run = |rounds: Rounds, payoff: RoundPayoff| {
+            let mut grid = Grid::with_seed(8, 8, 2);
+            grid.set_strategies(&"all-c=1,all-d=1".parse().unwrap(), StrategyLayout::Random, 1);
+            grid.encounter = Encounter { rounds, payoff };
+            grid.step();
+            grid
+        };
+        let single = run(Rounds::Fixed(1), RoundPayoff::Accumulate);
+        let summed = run(Rounds::Fixed(3), RoundPayoff::Accumulate);
+        let averaged = run(Rounds::Fixed(3), RoundPayoff::Average);
+
+        // Same pairings, three rounds each, and unconditional strategies repeat their moves
+        assert_eq!(summed.pass_stats.num_interactions, single.pass_stats.num_interactions);
+        assert_eq!(summed.pass_stats.num_updates, 3 * single.pass_stats.num_updates);
+        for cell in 0..64 {
+            let gain = single.agents[cell].fitness - INITIAL_FITNESS;
+            assert!((summed.agents[cell].fitness - INITIAL_FITNESS - 3.0 * gain).abs() < 1e-3);
+            assert!((averaged.agents[cell].fitness - single.agents[cell].fitness).abs() < 1e-3);
+        }
+
+        // Geometric encounters end on their own; learners also see memory grow between rounds
+        let mut grid = Grid::with_seed(8, 8, 2);
+        grid.encounter.rounds = Rounds::Geometric(0.8);
+        grid.step();
+        assert!(grid.pass_stats.num_updates > 2 * grid.pass_stats.num_interactions);
+        
+        // Greedy cooperators with one-game memories revisit one state every
+        // round, and each round learns from the one before
+        let learned_q = |rounds: u32| {
+            let mut grid = Grid::with_seed(2, 1, 3);
+            grid.encounter.rounds = Rounds::Fixed(rounds);
+            for agent in &mut grid.agents {
+                agent.resize_memory(1);
+                agent.memory_bits = 0;
+                agent.epsilon = 0.0;
+            }
+            let key = grid.policy_key(&grid.agents[0], grid.state_encoder.encode(&grid.agents[0], &grid.agents[1]));
+            let mut policy = grid.policy_table.get_or_create(key);
+            policy.q_values = [1.0, 0.0, 0.0, 0.0];
+            grid.policy_table.update(key, policy);
+            grid.step();
+            grid.policy_table.get_or_create(key).q_values[Action::Cooperate as usize]
+        };
+        assert!(learned_q(10) > learned_q(2));
+        assert!(learned_q(2) > learned_q(1));
+    }
+
+    #[test]
+    fn test_evolution_between_lifetimes() {
+        for rule in [EvolutionRule::DeathBirth, EvolutionRule::BirthDeath, EvolutionRule::Fermi] {
+            let mut grid = Grid::with_seed(10, 10, 9);
+            grid.merge_rule = MergeRule::reference();
+            grid.set_vacancies(0.2);
+            grid.evolution = Some(Evolution { rule, selection: 1.0, period: 5 });
+            let occupied = grid.active_mask.count_ones();
+
+            let mut replacements = 0;
+            for _ in 0..30 {
+                grid.step();
+                replacements += grid.pass_stats.replacements;
+            }
+            assert!(replacements > 0, "{:?}", rule);
+            assert!(grid.get_statistics().multicellular_agents > 0);
+
+            // Only birth-death places offspring on vacant cells
+            let now_occupied = grid.active_mask.count_ones();
+            if rule == EvolutionRule::BirthDeath {
+                assert!(now_occupied > occupied);
+            } else {
+                assert_eq!(now_occupied, occupied);
+            }
+            for cell in grid.active_mask.iter_ones() {
+                let root = grid.find_root(cell);
+                assert_eq!(grid.root_cache[cell] as usize, root);
+                assert!(grid.member_cells(root).contains(&cell));
+            }
+            
+            // The reference step runs generations too, counting games as timesteps
+            let mut grid = Grid::with_seed(10, 10, 9);
+            grid.evolution = Some(Evolution { rule, selection: 1.0, period: 5 });
+            let replacements: usize = (0..30)
+                .map(|_| {
+                    grid.step_reference();
+                    grid.pass_stats.replacements
+                })
+                .sum();
+            assert!(replacements > 0, "{:?}", rule);
+        }
+    }
+
+    #[test]
+    fn test_topologies_bound_interactions() {
+        let graph: GraphSpec = "watts-strogatz:48:4:0.2".parse().unwrap();
+        for (topology, width, height) in [
+            (Topology::Lattice { lattice: Lattice::VonNeumann, radius: 1, wrap: false }, 8, 6),
+            (Topology::Lattice { lattice: Lattice::Hex, radius: 1, wrap: true }, 8, 6),
+            (Topology::Lattice { lattice: Lattice::Moore, radius: 2, wrap: true }, 8, 6),
+            (Topology::Graph(Arc::new(graph.build(5).unwrap())), 48, 1),
+        ] {
+            let mut grid = Grid::with_seed(width, height, 5);
+            grid.merge_rule = MergeRule::reference();
+            grid.set_topology(topology.clone());
+            grid.set_interaction_scope(InteractionScope::Organism);
+            let mut neighbors = Vec::new();
+            for _ in 0..10 {
+                grid.step();
+            }
+            // Every merge joined neighbors, so each organism is connected in the topology
+            assert!(grid.get_statistics().multicellular_agents > 0);
+            for root in grid.active_roots() {
+                let cells = grid.member_cells(root);
+                let mut reached = vec![cells[0]];
+                let mut frontier = vec![cells[0]];
+                while let Some(cell) = frontier.pop() {
+                    grid.get_neighbors(cell, &mut neighbors);
+                    for &n in &neighbors {
+                        if cells.contains(&n) && !reached.contains(&n) {
+                            reached.push(n);
+                            frontier.push(n);
+                        }
+                    }
+                }
+                assert_eq!(reached.len(), cells.len(), "{:?}", topology);
+            }
+        }
+    }
+    
+    #[test]
+    fn test_fixed_strategies_in_patches() {
+        let mut grid = Grid::with_seed(8, 8, 4);
+        grid.set_strategies(&"all-d=1,tft=1".parse().unwrap(), StrategyLayout::Patches, 2);
+        for (idx, agent) in grid.agents.iter().enumerate() {
+            let (x, y) = (idx % 8, idx / 8);
+            assert_eq!(agent.strategy, grid.agents[(y / 2 * 2) * 8 + x / 2 * 2].strategy);
+        }
+        
+        grid.step();
+        let stats = grid.get_statistics();
+        let alld = stats.by_strategy[Strategy::AllD as usize];
+        let tft = stats.by_strategy[Strategy::Tft as usize];
+        assert_eq!(alld.cells + tft.cells, stats.total_agents);
+        assert!(alld.cells > 0 && tft.cells > 0);
+        assert_eq!(alld.cooperators, 0);
+        // Everyone opens against a clean memory, so TFT cooperates in its first games
+        assert!(tft.cooperators > 0);
+        assert!(grid.agents.iter().all(|agent| agent.strategy != Strategy::AllD || agent.last_action == Action::Defect as u8));
+    }
+    
+    #[test]
+    fn test_seeded_runs_match_across_thread_counts() {
+        let run = |threads: usize, inheritance: MergeInheritance| {
+            let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+            pool.install(|| {
+                let mut grid = Grid::with_seed(20, 20, 1234);
+                grid.merge_rule.trigger = MergeTrigger::Either;
+                grid.merge_rule.inheritance = inheritance;
+                let mut fitness = Vec::new();
+                for _ in 0..20 {
+                    grid.step();
+                    fitness.push(grid.get_statistics().total_fitness.to_bits());
+                }
+                let owners: Vec<_> = grid.agents.iter().map(|agent| (agent.policy_owner, agent.memory_bits)).collect();
+                (fitness, grid.agents.len(), owners)
+            })
+        };
+        
+        // Random and fitness-weighted inheritance draw from each game's own stream, so
+        // repeated merges of one multicellular pair can pick different parents
+        for inheritance in [MergeInheritance::Fitter, MergeInheritance::Random, MergeInheritance::FitnessWeighted] {
+            assert_eq!(run(1, inheritance), run(4, inheritance), "{:?}", inheritance);
+        }
+    }
+    
+    #[test]
+    fn test_every_merge_commits_on_large_grids() {
+        // More merges than the old fixed-size queue could hold
+        let mut grid = Grid::with_seed(1001, 1000, 3);
+        grid.merge_rule = MergeRule::reference();
+        for agent in &mut grid.agents {
+            agent.strategy = Strategy::MergeHappy;
+        }
+        grid.step();
+        assert!(grid.pass_stats.num_interactions > 1_000_000);
+        assert_eq!(grid.pass_stats.num_deferred_ops, grid.pass_stats.num_interactions);
+    }
+    
     #[test]
     fn test_large_grid_creation() {
         let grid = Grid::new(1000, 1000);
@@ -0,0 +1,88 @@
+use crate::agent::{Agent, MAX_MEM_LENGTH};
+use rand::Rng;
+
+/// Which memory length a merged agent gets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum MemoryInheritance {
+    /// Whatever the parent passing on memory had (the JS `clone`)
+    Inherited,
+    /// The longer of the two parents' memories
+    Max,
+    /// The shorter of the two parents' memories
+    Min,
+    /// The memory length of the parent that was fitter before the merging
+    /// game, ties going to the first player
+    Fitter,
+}
+
+/// How memory length is drawn and evolves through merges and splits
+#[derive(Debug, Clone, Copy)]
+pub struct MemoryRule {
+    pub inheritance: MemoryInheritance,
+    /// Chance that each parent's length moves one step up or down on a split
+    pub mutation: f32,
+    /// Longest allowed memory (JS `MAX_MEM`), at most `MAX_MEM_LENGTH`
+    pub max_length: u8,
+}
+
+impl Default for MemoryRule {
+    fn default() -> Self {
+        Self { inheritance: MemoryInheritance::Inherited, mutation: 0.0, max_length: 5 }
+    }
+}
+
+impl MemoryRule {
+    /// Initial memory length, uniform in 1..=max_length
+    pub fn draw<R: Rng>(&self, rng: &mut R) -> u8 {
+        rng.gen_range(1..=self.max_length.clamp(1, MAX_MEM_LENGTH))
+    }
+
+    /// Memory length of an agent merged from `a` and `b`, given the lengths
+    /// of the parent it inherited from and of the parent that was fitter
+    pub fn merged(&self, inherited: u8, fitter: u8, a: &Agent, b: &Agent) -> u8 {
+        match self.inheritance {
+            MemoryInheritance::Inherited => inherited,
+            MemoryInheritance::Max => a.mem_length.max(b.mem_length),
+            MemoryInheritance::Min => a.mem_length.min(b.mem_length),
+            MemoryInheritance::Fitter => fitter,
+        }
+    }
+
+    /// Length passed to a parent on a split, mutated with probability `mutation`
+    pub fn mutate<R: Rng>(&self, length: u8, rng: &mut R) -> u8 {
+        if self.mutation <= 0.0 || rng.gen::<f32>() >= self.mutation {
+            return length;
+        }
+        let stepped = if rng.gen::<bool>() { length.saturating_add(1) } else { length.saturating_sub(1) };
+        stepped.clamp(1, self.max_length.clamp(1, MAX_MEM_LENGTH))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_merged_lengths_and_mutation_bounds() {
+        let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(0);
+        let (mut a, mut b) = (Agent::new(0, &mut rng), Agent::new(1, &mut rng));
+        (a.mem_length, b.mem_length) = (2, 4);
+
+        let mut rule = MemoryRule::default();
+        assert_eq!(rule.merged(3, 5, &a, &b), 3);
+        rule.inheritance = MemoryInheritance::Max;
+        assert_eq!(rule.merged(3, 5, &a, &b), 4);
+        rule.inheritance = MemoryInheritance::Min;
+        assert_eq!(rule.merged(3, 5, &a, &b), 2);
+        rule.inheritance = MemoryInheritance::Fitter;
+        assert_eq!(rule.merged(3, 5, &a, &b), 5);
+
+        rule.mutation = 1.0;
+        rule.max_length = 3;
+        for _ in 0..100 {
+            assert!([2, 3].contains(&rule.mutate(3, &mut rng)));
+            assert!([1, 2].contains(&rule.mutate(1, &mut rng)));
+        }
+    }
+}
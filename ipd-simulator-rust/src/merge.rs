@@ -0,0 +1,140 @@
+use crate::agent::Action;
+use rand::Rng;
+
+/// Which joint actions turn a game into a merge
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum MergeTrigger {
+    /// Unilateral: either player choosing Merge is enough (JS `fight()`)
+    Either,
+    /// Mutual consent: both players must choose Merge
+    Both,
+    /// Each Merge action succeeds independently with `merge_probability`
+    Probabilistic,
+}
+
+/// How the merged agent's fitness is derived from its parents'
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum FitnessCombine {
+    Sum,
+    Mean,
+    Max,
+}
+
+impl FitnessCombine {
+    pub fn combine(self, a: f32, b: f32) -> f32 {
+        match self {
+            FitnessCombine::Sum => a + b,
+            FitnessCombine::Mean => (a + b) / 2.0,
+            FitnessCombine::Max => a.max(b),
+        }
+    }
+}
+
+/// Which parent the merged agent inherits memory and policy from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum MergeInheritance {
+    /// The fitter parent, ties going to the first player (JS `indexOfMax`)
+    Fitter,
+    /// A uniformly random parent
+    Random,
+    /// Memory and policy each come whole from one parent, drawn independently
+    /// with probability proportional to the parent's fitness; nothing is averaged
+    FitnessWeighted,
+}
+
+/// Complete merge configuration
+#[derive(Debug, Clone, Copy)]
+pub struct MergeRule {
+    pub trigger: MergeTrigger,
+    pub merge_probability: f32,
+    pub fitness: FitnessCombine,
+    pub inheritance: MergeInheritance,
+}
+
+impl Default for MergeRule {
+    /// Mutual consent, summed fitness, fitter parent
+    fn default() -> Self {
+        Self {
+            trigger: MergeTrigger::Both,
+            merge_probability: 1.0,
+            fitness: FitnessCombine::Sum,
+            inheritance: MergeInheritance::Fitter,
+        }
+    }
+}
+
+/// Index of the fitter of two `(index, fitness)` players, ties going to the first
+pub fn fitter(a: (u32, f32), b: (u32, f32)) -> u32 {
+    if b.1 > a.1 { b.0 } else { a.0 }
+}
+
+impl MergeRule {
+    /// The rule used by grid-copy.js and algorithm.md §5.1
+    pub fn reference() -> Self {
+        Self {
+            trigger: MergeTrigger::Either,
+            merge_probability: 1.0,
+            fitness: FitnessCombine::Mean,
+            inheritance: MergeInheritance::Fitter,
+        }
+    }
+
+    /// Decide whether a game with these actions merges the two players
+    pub fn triggers<R: Rng>(&self, a: Action, b: Action, rng: &mut R) -> bool {
+        match self.trigger {
+            MergeTrigger::Either => a == Action::Merge || b == Action::Merge,
+            MergeTrigger::Both => a == Action::Merge && b == Action::Merge,
+            MergeTrigger::Probabilistic => [a, b]
+                .into_iter()
+                .filter(|&action| action == Action::Merge)
+                .any(|_| rng.gen::<f32>() < self.merge_probability),
+        }
+    }
+
+    /// Pick the parents that pass on (memory, policy) to the merged agent
+    pub fn pick_parents<R: Rng>(&self, a: (u32, f32), b: (u32, f32), rng: &mut R) -> (u32, u32) {
+        match self.inheritance {
+            MergeInheritance::Fitter => {
+                let fitter = fitter(a, b);
+                (fitter, fitter)
+            }
+            MergeInheritance::Random => {
+                let chosen = if rng.gen::<bool>() { a.0 } else { b.0 };
+                (chosen, chosen)
+            }
+            MergeInheritance::FitnessWeighted => {
+                let total = a.1.max(0.0) + b.1.max(0.0);
+                let p_a = if total > 0.0 { a.1.max(0.0) / total } else { 0.5 };
+                let mut draw = || if rng.gen::<f32>() < p_a { a.0 } else { b.0 };
+                (draw(), draw())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_triggers() {
+        let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(0);
+        let either = MergeRule::reference();
+        let both = MergeRule::default();
+        assert!(either.triggers(Action::Merge, Action::Defect, &mut rng));
+        assert!(!both.triggers(Action::Merge, Action::Defect, &mut rng));
+        assert!(both.triggers(Action::Merge, Action::Merge, &mut rng));
+
+        let never = MergeRule { trigger: MergeTrigger::Probabilistic, merge_probability: 0.0, ..both };
+        assert!(!never.triggers(Action::Merge, Action::Merge, &mut rng));
+    }
+
+    #[test]
+    fn test_fitter_parent_wins_ties_for_first_player() {
+        let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(0);
+        let rule = MergeRule::reference();
+        assert_eq!(rule.pick_parents((3, 1.0), (4, 1.0), &mut rng), (3, 3));
+        assert_eq!(rule.pick_parents((3, 1.0), (4, 2.0), &mut rng), (4, 4));
+    }
+}
@@ -0,0 +1,136 @@
+use crate::agent::Agent;
+use crate::grid::Grid;
+use crate::rng::{self, DOMAIN_MIGRATION, DOMAIN_VACANCY};
+use rand::Rng;
+use rayon::prelude::*;
+
+impl Grid {
+    /// Empty each grid cell independently with probability `density`,
+    /// which must be below 1.
+    ///
+    /// Vacant cells hold no agent: their slot keeps an `Agent::vacant`
+    /// placeholder, and they never play, merge or show up in statistics
+    /// until a migrating agent moves in. Must be called before any merge
+    /// happens.
+    pub fn set_vacancies(&mut self, density: f64) {
+        let cell_count = self.grid_width * self.grid_height;
+        assert_eq!(self.agents.len(), cell_count, "vacancies must be set before the first merge");
+        assert!((0.0..1.0).contains(&density), "vacancy density must be in [0, 1), got {}", density);
+
+        for cell in 0..cell_count {
+            let mut rng = rng::stream(self.seed, 0, DOMAIN_VACANCY, cell as u64);
+            let occupied = rng.gen::<f64>() >= density;
+            self.active_mask.set(cell, occupied);
+            if !occupied {
+                self.agents[cell] = Agent::vacant(cell as u32);
+            }
+        }
+    }
+
+    /// Whether agent `idx` occupies the grid: super-agents always do, cell
+    /// agents unless their cell is vacant
+    #[inline]
+    pub fn is_occupied(&self, idx: usize) -> bool {
+        idx >= self.grid_width * self.grid_height || self.active_mask[idx]
+    }
+
+    /// Move single-cell agents onto empty neighboring cells.
+    ///
+    /// Each single-cell agent decides with probability `migration_rate` to
+    /// move and picks a random vacant neighbor, all against the grid as it
+    /// stood before anyone moved. Moves then commit in cell order, and a
+    /// cell claimed by an earlier mover stays put for later ones, so the
+    /// result never depends on thread scheduling. Organisms stay where they
+    /// are. Returns the number of agents that moved.
+    pub(crate) fn migrate(&mut self) -> usize {
+        if self.migration_rate <= 0.0 {
+            return 0;
+        }
+
+        let cell_count = self.grid_width * self.grid_height;
+        let moves: Vec<(usize, usize)> = (0..cell_count)
+            .into_par_iter()
+            .map_init(
+                || Vec::with_capacity(8),
+                |neighbors, cell| {
+                    if !self.active_mask[cell] || self.root_cache[cell] as usize != cell {
+                        return None;
+                    }
+                    let mut rng = rng::stream(self.seed, self.timestep, DOMAIN_MIGRATION, cell as u64);
+                    if rng.gen::<f32>() >= self.migration_rate {
+                        return None;
+                    }
+                    self.get_neighbors(cell, neighbors);
+                    neighbors.retain(|&n| !self.active_mask[n]);
+                    (!neighbors.is_empty()).then(|| (cell, neighbors[rng.gen_range(0..neighbors.len())]))
+                },
+            )
+            .flatten()
+            .collect();
+
+        let mut moved = 0;
+        for (from, to) in moves {
+            if self.active_mask[to] {
+                continue;
+            }
+            self.move_agent(from, to);
+            moved += 1;
+        }
+        moved
+    }
+
+    /// Move the single-cell agent in cell `from` to the vacant cell `to`.
+    ///
+    /// The agent takes the id of its new cell but keeps its policy owner,
+    /// so a per-agent Q-table travels with it. The cell it leaves gets a
+    /// vacant placeholder, so no stale copy stays behind.
+    fn move_agent(&mut self, from: usize, to: usize) {
+        let vacant = Agent::vacant(from as u32);
+        let mut agent = std::mem::replace(&mut self.agents[from], vacant);
+        agent.id = to as u32;
+        self.agents[to] = agent;
+        self.active_mask.set(from, false);
+        self.active_mask.set(to, true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reference::ReferenceStep;
+
+    #[test]
+    fn test_migrants_move_onto_vacant_cells() {
+        let mut grid = Grid::with_seed(10, 10, 8);
+        grid.set_vacancies(0.5);
+        let occupied = grid.active_mask.count_ones();
+        assert!((30..70).contains(&occupied));
+
+        grid.migration_rate = 1.0;
+        let mut owners: Vec<u32> = grid.active_mask.iter_ones().map(|cell| grid.agents[cell].policy_owner).collect();
+        let moved = grid.migrate();
+        assert!(moved > 0);
+        assert_eq!(grid.active_mask.count_ones(), occupied);
+
+        // Every agent is still on the grid exactly once, under its own id
+        let mut moved_owners: Vec<u32> = grid.active_mask.iter_ones().map(|cell| grid.agents[cell].policy_owner).collect();
+        moved_owners.sort_unstable();
+        owners.sort_unstable();
+        assert_eq!(owners, moved_owners);
+        assert!(grid.active_mask.iter_ones().all(|cell| grid.agents[cell].id as usize == cell));
+
+        // Vacated cells keep no copy of the agent that left
+        for cell in grid.active_mask.iter_zeros() {
+            assert_eq!(grid.agents[cell].policy_owner as usize, cell);
+            assert_eq!(grid.agents[cell].fitness, 0.0);
+        }
+    }
+
+    #[test]
+    fn test_empty_grid_plays_no_reference_game() {
+        let mut grid = Grid::with_seed(3, 3, 1);
+        grid.active_mask.fill(false);
+        assert_eq!(grid.step_reference(), ReferenceStep::Empty);
+        assert_eq!(grid.pass_stats.num_interactions, 0);
+    }
+}
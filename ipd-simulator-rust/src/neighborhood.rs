@@ -0,0 +1,108 @@
+use std::collections::HashSet;
+
+/// Who picks opponents each timestep
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum InteractionScope {
+    /// Every grid cell draws one random neighboring cell, so large organisms play more
+    Cell,
+    /// Every organism draws opponents from its boundary, like JS `getsupernbrs`
+    Organism,
+}
+
+/// Boundary cells of every agent, indexed by agent id.
+///
+/// An agent's cell set never changes after it is created, so neither does
+/// its boundary: a merge stores the union of both parents' boundaries minus
+/// their cells, and a split simply makes the parents' stored entries live
+/// again. Entries are only rewritten when a retired agent's slot is reused.
+pub struct NeighborhoodIndex {
+    boundaries: Vec<Vec<u32>>,
+}
+
+impl NeighborhoodIndex {
+    /// Start with one entry per grid cell: its lattice neighbors
+    pub fn new(cell_count: usize, neighbors_of: impl Fn(usize, &mut Vec<usize>)) -> Self {
+        let mut neighbors = Vec::with_capacity(8);
+        let boundaries = (0..cell_count)
+            .map(|cell| {
+                neighbors_of(cell, &mut neighbors);
+                neighbors.iter().map(|&n| n as u32).collect()
+            })
+            .collect();
+        Self { boundaries }
+    }
+
+    /// Cells bordering an agent's organism
+    pub fn boundary(&self, agent: usize) -> &[u32] {
+        &self.boundaries[agent]
+    }
+
+    /// Record the boundary of agent `new_id`, merged from `parent1` and `parent2`.
+    ///
+    /// `new_id` is either a reused slot or the next id. `is_member` tells
+    /// whether a cell belongs to either parent.
+    pub fn on_merge(&mut self, new_id: usize, parent1: usize, parent2: usize, is_member: impl Fn(u32) -> bool) {
+        let mut seen = HashSet::new();
+        let boundary = self.boundaries[parent1]
+            .iter()
+            .chain(&self.boundaries[parent2])
+            .copied()
+            .filter(|&cell| !is_member(cell) && seen.insert(cell))
+            .collect();
+        if new_id < self.boundaries.len() {
+            self.boundaries[new_id] = boundary;
+        } else {
+            debug_assert_eq!(new_id, self.boundaries.len());
+            self.boundaries.push(boundary);
+        }
+    }
+
+    /// Unique roots around an organism, in order of first appearance
+    pub fn neighbor_roots(&self, root: usize, root_cache: &[u32]) -> Vec<u32> {
+        let mut roots = Vec::new();
+        for &cell in self.boundary(root) {
+            let opp_root = root_cache[cell as usize];
+            if opp_root as usize != root && !roots.contains(&opp_root) {
+                roots.push(opp_root);
+            }
+        }
+        roots
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(n: usize) -> impl Fn(usize, &mut Vec<usize>) {
+        move |cell, out: &mut Vec<usize>| {
+            out.clear();
+            if cell > 0 {
+                out.push(cell - 1);
+            }
+            if cell + 1 < n {
+                out.push(cell + 1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_merge_and_split_boundaries() {
+        // Cells 0-1-2-3 in a row; merge 1+2 into agent 4, then 4+3 into agent 5
+        let mut index = NeighborhoodIndex::new(4, line(4));
+        let mut root_cache = vec![0, 1, 2, 3];
+
+        index.on_merge(4, 1, 2, |c| c == 1 || c == 2);
+        assert_eq!(index.boundary(4), &[0, 3]);
+        root_cache[1] = 4;
+        root_cache[2] = 4;
+        assert_eq!(index.neighbor_roots(4, &root_cache), vec![0, 3]);
+
+        index.on_merge(5, 4, 3, |c| root_cache[c as usize] == 4 || c == 3);
+        assert_eq!(index.boundary(5), &[0]);
+
+        // After splitting 5, the parents' entries still describe them
+        assert_eq!(index.boundary(4), &[0, 3]);
+        assert_eq!(index.boundary(3), &[2]);
+    }
+}
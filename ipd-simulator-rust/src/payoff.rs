@@ -0,0 +1,240 @@
+use crate::agent::Action;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+
+const ACTION_LETTERS: [char; 4] = ['C', 'D', 'M', 'S'];
+
+/// Payoff table for IPD game, indexed by (my action, opponent action)
+#[derive(Debug, Clone, PartialEq)]
+pub struct PayoffTable {
+    table: [[f32; 4]; 4],
+}
+
+/// Named Prisoner's Dilemma payoffs plus the payoffs of the extra actions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DilemmaPayoffs {
+    /// Temptation: my Defect against Cooperate
+    pub t: f32,
+    /// Reward: mutual cooperation
+    pub r: f32,
+    /// Punishment: mutual defection
+    pub p: f32,
+    /// Sucker: my Cooperate against Defect
+    pub s: f32,
+    /// Each player's payoff when both choose Merge
+    pub merge: f32,
+    /// Any game in which either player chooses Split
+    pub split: f32,
+}
+
+/// Well-known T/R/P/S settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum PayoffPreset {
+    /// The `payofftable` defaults in grid-copy.js (T=10, R=8, P=5, S=0)
+    Js,
+    /// Axelrod's tournament payoffs (T=5, R=3, P=1, S=0)
+    Axelrod,
+    /// Snowdrift / hawk-dove ordering T > R > S > P
+    Snowdrift,
+    /// Stag hunt ordering R > T > P > S
+    StagHunt,
+}
+
+impl PayoffPreset {
+    pub fn payoffs(self) -> DilemmaPayoffs {
+        let (t, r, p, s) = match self {
+            PayoffPreset::Js => (10.0, 8.0, 5.0, 0.0),
+            PayoffPreset::Axelrod => (5.0, 3.0, 1.0, 0.0),
+            PayoffPreset::Snowdrift => (4.0, 3.0, 0.0, 2.0),
+            PayoffPreset::StagHunt => (3.0, 5.0, 1.0, 0.0),
+        };
+        DilemmaPayoffs { t, r, p, s, merge: 0.0, split: 0.0 }
+    }
+}
+
+/// Accepted payoff file layouts
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PayoffFile {
+    /// 16 entries, rows and columns in C, D, M, S order
+    Matrix(Vec<f32>),
+    /// Either JS `payofftable` keys ("CC", "CD", ...) or T/R/P/S names
+    Named(HashMap<String, f32>),
+}
+
+impl PayoffTable {
+    pub fn default() -> Self {
+        Self::from_dilemma(PayoffPreset::Js.payoffs())
+    }
+
+    /// Build the 4x4 table from T/R/P/S.
+    ///
+    /// Merge counts as cooperation towards a player who does not merge, as
+    /// in the JS defaults: CM = MC = R, DM = T and MD = S.
+    pub fn from_dilemma(p: DilemmaPayoffs) -> Self {
+        let x = p.split;
+        Self {
+            table: [
+                //  C    D    M        S
+                [p.r, p.s, p.r,     x], // C
+                [p.t, p.p, p.t,     x], // D
+                [p.r, p.s, p.merge, x], // M
+                [x,   x,   x,       x], // S
+            ],
+        }
+    }
+
+    /// Build the table from 16 row-major entries in C, D, M, S order
+    pub fn from_entries(entries: &[f32]) -> Result<Self, Box<dyn Error>> {
+        if entries.len() != 16 {
+            return Err(format!("payoff matrix needs 16 entries, got {}", entries.len()).into());
+        }
+        let mut table = [[0.0; 4]; 4];
+        for (i, &value) in entries.iter().enumerate() {
+            table[i / 4][i % 4] = value;
+        }
+        let table = Self { table };
+        table.validate()?;
+        Ok(table)
+    }
+
+    /// Load a JSON payoff file: a 16-entry array, a JS-style `payofftable`
+    /// object, or an object of `T`, `R`, `P`, `S` and optional `merge`/`split`
+    pub fn from_file(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("cannot read payoff file {}: {}", path.display(), e))?;
+        let parsed: PayoffFile = serde_json::from_str(&text)
+            .map_err(|e| format!("invalid payoff file {}: {}", path.display(), e))?;
+        match parsed {
+            PayoffFile::Matrix(entries) => Self::from_entries(&entries),
+            PayoffFile::Named(map) => Self::from_named(&map),
+        }
+    }
+
+    fn from_named(map: &HashMap<String, f32>) -> Result<Self, Box<dyn Error>> {
+        if map.keys().any(|key| key.len() == 2) {
+            let mut entries = Vec::with_capacity(16);
+            for my in ACTION_LETTERS {
+                for opp in ACTION_LETTERS {
+                    let key = format!("{}{}", my, opp);
+                    entries.push(*map.get(&key).ok_or_else(|| format!("payoff file is missing \"{}\"", key))?);
+                }
+            }
+            if let Some(key) = map.keys().find(|key| !is_pair_key(key)) {
+                return Err(format!("unknown payoff key \"{}\"", key).into());
+            }
+            return Self::from_entries(&entries);
+        }
+
+        let required = |name: &str| map.get(name).copied().ok_or_else(|| format!("payoff file is missing \"{}\"", name));
+        if let Some(key) = map.keys().find(|key| !["T", "R", "P", "S", "merge", "split"].contains(&key.as_str())) {
+            return Err(format!("unknown payoff key \"{}\"", key).into());
+        }
+        let table = Self::from_dilemma(DilemmaPayoffs {
+            t: required("T")?,
+            r: required("R")?,
+            p: required("P")?,
+            s: required("S")?,
+            merge: map.get("merge").copied().unwrap_or(0.0),
+            split: map.get("split").copied().unwrap_or(0.0),
+        });
+        table.validate()?;
+        Ok(table)
+    }
+
+    /// Reject entries that would poison fitness and Q-values
+    pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+        for (i, row) in self.table.iter().enumerate() {
+            for (j, value) in row.iter().enumerate() {
+                if !value.is_finite() {
+                    return Err(format!(
+                        "payoff {}{} is not a finite number: {}",
+                        ACTION_LETTERS[i], ACTION_LETTERS[j], value
+                    )
+                    .into());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the C/D block satisfies T > R > P > S and 2R > T + S
+    pub fn is_prisoners_dilemma(&self) -> bool {
+        let (t, r, p, s) = (self.table[1][0], self.table[0][0], self.table[1][1], self.table[0][1]);
+        t > r && r > p && p > s && 2.0 * r > t + s
+    }
+
+    pub fn get(&self, my_action: Action, opp_action: Action) -> f32 {
+        self.table[my_action as usize][opp_action as usize]
+    }
+}
+
+fn is_pair_key(key: &str) -> bool {
+    key.len() == 2 && key.chars().all(|c| ACTION_LETTERS.contains(&c))
+}
+
+impl fmt::Display for PayoffTable {
+    /// Row player's payoff, one row per own action
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "     ")?;
+        for letter in ACTION_LETTERS {
+            write!(f, "{:>8}", letter)?;
+        }
+        for (letter, row) in ACTION_LETTERS.iter().zip(&self.table) {
+            write!(f, "\n  {}  ", letter)?;
+            for value in row {
+                write!(f, "{:>8.2}", value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_js_preset_matches_grid_copy_defaults() {
+        let table = PayoffTable::default();
+        let js = [8.0, 0.0, 8.0, 0.0, 10.0, 5.0, 10.0, 0.0, 8.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0];
+        assert_eq!(table, PayoffTable::from_entries(&js).unwrap());
+        assert!(table.is_prisoners_dilemma());
+        assert!(!PayoffTable::from_dilemma(PayoffPreset::Snowdrift.payoffs()).is_prisoners_dilemma());
+    }
+
+    #[test]
+    fn test_named_payoffs() {
+        let mut js: HashMap<String, f32> = HashMap::new();
+        for (i, my) in ACTION_LETTERS.iter().enumerate() {
+            for (j, opp) in ACTION_LETTERS.iter().enumerate() {
+                js.insert(format!("{}{}", my, opp), (i * 4 + j) as f32);
+            }
+        }
+        let table = PayoffTable::from_named(&js).unwrap();
+        assert_eq!(table.get(Action::Merge, Action::Defect), 9.0);
+
+        js.remove("SS");
+        assert!(PayoffTable::from_named(&js).is_err());
+
+        let trps: HashMap<String, f32> = [("T", 5.0), ("R", 3.0), ("P", 1.0), ("S", 0.0), ("split", -1.0)]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect();
+        let table = PayoffTable::from_named(&trps).unwrap();
+        assert_eq!(table.get(Action::Defect, Action::Cooperate), 5.0);
+        assert_eq!(table.get(Action::Cooperate, Action::Split), -1.0);
+        assert_eq!(table.get(Action::Merge, Action::Merge), 0.0);
+    }
+
+    #[test]
+    fn test_rejects_bad_matrices() {
+        assert!(PayoffTable::from_entries(&[1.0; 15]).is_err());
+        let mut entries = [1.0; 16];
+        entries[5] = f32::NAN;
+        assert!(PayoffTable::from_entries(&entries).is_err());
+    }
+}
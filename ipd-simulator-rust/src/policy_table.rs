@@ -0,0 +1,406 @@
+use crate::agent::CompactPolicy;
+use crate::grid::{PolicyKey, SHARED_POLICY_OWNER};
+use crate::rng::{self, DOMAIN_POLICY_INIT};
+use rustc_hash::{FxHashMap, FxHashSet, FxHasher};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::hash::{Hash, Hasher};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::RwLock;
+
+const SHARDS: usize = 64;
+
+/// Which policies a bounded table drops first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum PolicyEviction {
+    /// The states used longest ago
+    Lru,
+    /// The states whose actions were tried least often
+    LeastVisited,
+}
+
+/// Start of binary policy files, followed by a format version
+const BINARY_MAGIC: &[u8; 4] = b"IPDQ";
+const BINARY_VERSION: u32 = 1;
+// owner, state, q_values, q_values_b, visits
+const BINARY_RECORD_BYTES: usize = 4 + 8 + 16 + 16 + 16;
+
+/// One stored state in a JSON policy file
+#[derive(Serialize, Deserialize)]
+struct PolicyRecord {
+    owner: u32,
+    state: u64,
+    #[serde(flatten)]
+    policy: CompactPolicy,
+}
+
+struct PolicyEntry {
+    policy: CompactPolicy,
+    // Timestep of the latest read or write; `fetch_max` keeps it independent of thread order
+    last_used: AtomicU64,
+}
+
+/// Policy table sharded over read-write locked hash maps.
+///
+/// Per-agent tables live in the same map, partitioned by `PolicyKey::owner`.
+/// Handing a table to another agent is just copying its owner id, which is
+/// how the JS reference shares `policy` objects on merge and split.
+///
+/// A bounded table may grow past its capacity during a timestep and is
+/// trimmed back between timesteps, so eviction never races with learning.
+pub struct PolicyTable {
+    shards: Vec<RwLock<FxHashMap<PolicyKey, PolicyEntry>>>,
+    seed: u64,
+    capacity: Option<usize>,
+    eviction: PolicyEviction,
+    clock: u64,
+    inserts: AtomicUsize,
+    // One past the highest per-agent owner loaded from a file
+    owner_floor: u32,
+}
+
+impl PolicyTable {
+    /// Table that keeps every state it has seen
+    pub fn new(seed: u64) -> Self {
+        Self {
+            shards: (0..SHARDS).map(|_| RwLock::new(FxHashMap::default())).collect(),
+            seed,
+            capacity: None,
+            eviction: PolicyEviction::Lru,
+            clock: 0,
+            inserts: AtomicUsize::new(0),
+            owner_floor: 0,
+        }
+    }
+
+    /// Table trimmed to `capacity` states after every timestep
+    pub fn bounded(capacity: usize, eviction: PolicyEviction, seed: u64) -> Self {
+        Self { capacity: Some(capacity), eviction, ..Self::new(seed) }
+    }
+
+    fn shard(&self, key: &PolicyKey) -> &RwLock<FxHashMap<PolicyKey, PolicyEntry>> {
+        let mut hasher = FxHasher::default();
+        key.hash(&mut hasher);
+        &self.shards[(hasher.finish() >> 58) as usize % SHARDS]
+    }
+
+    /// Mark later reads and writes as happening at `timestep`
+    pub fn set_clock(&mut self, timestep: u64) {
+        self.clock = timestep;
+    }
+
+    pub fn get_or_create(&self, key: PolicyKey) -> CompactPolicy {
+        let shard = self.shard(&key);
+        if let Some(entry) = shard.read().unwrap().get(&key) {
+            entry.last_used.fetch_max(self.clock, Ordering::Relaxed);
+            return entry.policy;
+        }
+
+        let mut map = shard.write().unwrap();
+        let entry = map.entry(key).or_insert_with(|| {
+            self.inserts.fetch_add(1, Ordering::Relaxed);
+            PolicyEntry { policy: self.initial_policy(key), last_used: AtomicU64::new(self.clock) }
+        });
+        entry.last_used.fetch_max(self.clock, Ordering::Relaxed);
+        entry.policy
+    }
+
+    /// Stored policy for `key`, or the one `get_or_create` would insert,
+    /// without inserting it. Unseen states therefore play a random action.
+    pub fn get_or_initial(&self, key: PolicyKey) -> CompactPolicy {
+        match self.shard(&key).read().unwrap().get(&key) {
+            Some(entry) => {
+                entry.last_used.fetch_max(self.clock, Ordering::Relaxed);
+                entry.policy
+            }
+            None => self.initial_policy(key),
+        }
+    }
+
+    /// Initial Q-values depend only on the key, so evicted states come back the same
+    fn initial_policy(&self, key: PolicyKey) -> CompactPolicy {
+        let mut rng = rng::stream(self.seed, key.owner as u64, DOMAIN_POLICY_INIT, key.state);
+        CompactPolicy::new(&mut rng)
+    }
+
+    pub fn update(&self, key: PolicyKey, policy: CompactPolicy) {
+        let mut map = self.shard(&key).write().unwrap();
+        match map.get_mut(&key) {
+            Some(entry) => {
+                entry.policy = policy;
+                *entry.last_used.get_mut() = (*entry.last_used.get_mut()).max(self.clock);
+            }
+            None => {
+                self.inserts.fetch_add(1, Ordering::Relaxed);
+                map.insert(key, PolicyEntry { policy, last_used: AtomicU64::new(self.clock) });
+            }
+        }
+    }
+
+    /// Number of stored states
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|shard| shard.read().unwrap().len()).sum()
+    }
+
+    /// States added since the last call
+    pub fn take_inserts(&self) -> usize {
+        self.inserts.swap(0, Ordering::Relaxed)
+    }
+
+    /// Approximate heap size of the maps, including empty buckets
+    pub fn memory_bytes(&self) -> usize {
+        let bucket = std::mem::size_of::<(PolicyKey, PolicyEntry)>() + 1;
+        self.shards.iter().map(|shard| shard.read().unwrap().capacity() * bucket).sum()
+    }
+
+    /// Every stored state and its policy, sorted by key
+    pub fn entries(&self) -> Vec<(PolicyKey, CompactPolicy)> {
+        let mut entries: Vec<_> = self
+            .shards
+            .iter()
+            .flat_map(|shard| shard.read().unwrap().iter().map(|(key, entry)| (*key, entry.policy)).collect::<Vec<_>>())
+            .collect();
+        entries.sort_unstable_by_key(|(key, _)| (key.owner, key.state));
+        entries
+    }
+
+    /// Write the table to `path`: JSON if it ends in `.json`, the compact
+    /// binary format otherwise
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let entries = self.entries();
+        let file = std::fs::File::create(path)
+            .map_err(|e| format!("cannot create policy file {}: {}", path.display(), e))?;
+        let mut writer = BufWriter::new(file);
+        if is_json(path) {
+            let records: Vec<_> = entries
+                .into_iter()
+                .map(|(key, policy)| PolicyRecord { owner: key.owner, state: key.state, policy })
+                .collect();
+            serde_json::to_writer(&mut writer, &records)?;
+        } else {
+            writer.write_all(BINARY_MAGIC)?;
+            writer.write_all(&BINARY_VERSION.to_le_bytes())?;
+            writer.write_all(&(entries.len() as u64).to_le_bytes())?;
+            for (key, policy) in entries {
+                writer.write_all(&key.owner.to_le_bytes())?;
+                writer.write_all(&key.state.to_le_bytes())?;
+                for value in policy.q_values.iter().chain(&policy.q_values_b) {
+                    writer.write_all(&value.to_le_bytes())?;
+                }
+                for visits in policy.visits {
+                    writer.write_all(&visits.to_le_bytes())?;
+                }
+            }
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Add the states saved in `path` to the table, replacing any with the
+    /// same key. Returns how many were read.
+    pub fn load(&mut self, path: &Path) -> Result<usize, Box<dyn Error>> {
+        let bytes = std::fs::read(path)
+            .map_err(|e| format!("cannot read policy file {}: {}", path.display(), e))?;
+        let records = if is_json(path) {
+            serde_json::from_slice::<Vec<PolicyRecord>>(&bytes)
+                .map_err(|e| format!("invalid policy file {}: {}", path.display(), e))?
+        } else {
+            parse_binary(&bytes).map_err(|e| format!("invalid policy file {}: {}", path.display(), e))?
+        };
+        for record in &records {
+            self.update(PolicyKey { owner: record.owner, state: record.state }, record.policy);
+            if record.owner != SHARED_POLICY_OWNER {
+                self.owner_floor = self.owner_floor.max(record.owner + 1);
+            }
+        }
+        // Loaded states are not churn of the first timestep
+        self.take_inserts();
+        Ok(records.len())
+    }
+
+    /// Lowest owner id no loaded table uses
+    pub fn owner_floor(&self) -> u32 {
+        self.owner_floor
+    }
+
+    /// Copy every state of each source owner to the new owners mapped to it
+    /// (`new owner -> source owner`) and drop every state of the `dropped`
+    /// owners, in one pass over the table. Sources are copied before they
+    /// are dropped; new owners that are dropped get no copy.
+    pub fn copy_owners(&mut self, copies: &FxHashMap<u32, u32>, dropped: &FxHashSet<u32>) {
+        let mut targets: FxHashMap<u32, Vec<u32>> = FxHashMap::default();
+        for (&owner, &source) in copies {
+            if !dropped.contains(&owner) {
+                targets.entry(source).or_default().push(owner);
+            }
+        }
+        let mut copied = Vec::new();
+        for shard in &mut self.shards {
+            shard.get_mut().unwrap().retain(|key, entry| {
+                for &owner in targets.get(&key.owner).into_iter().flatten() {
+                    copied.push((PolicyKey { owner, state: key.state }, entry.policy, *entry.last_used.get_mut()));
+                }
+                !dropped.contains(&key.owner)
+            });
+        }
+        *self.inserts.get_mut() += copied.len();
+        for (key, policy, last_used) in copied {
+            self.shard(&key).write().unwrap().insert(key, PolicyEntry { policy, last_used: AtomicU64::new(last_used) });
+        }
+    }
+
+    /// Evict states until the table fits its capacity. Returns how many were dropped.
+    pub fn trim(&mut self) -> usize {
+        let Some(capacity) = self.capacity else {
+            return 0;
+        };
+        let excess = self.len().saturating_sub(capacity);
+        if excess == 0 {
+            return 0;
+        }
+
+        // Rank every state; keys break ties so the victims never depend on map order
+        let mut ranked: Vec<(u64, u64, u32, u64)> = Vec::with_capacity(self.len());
+        for shard in &mut self.shards {
+            for (key, entry) in shard.get_mut().unwrap().iter_mut() {
+                let last_used = *entry.last_used.get_mut();
+                let primary = match self.eviction {
+                    PolicyEviction::Lru => last_used,
+                    PolicyEviction::LeastVisited => entry.policy.visits.iter().map(|&n| n as u64).sum(),
+                };
+                ranked.push((primary, last_used, key.owner, key.state));
+            }
+        }
+        ranked.select_nth_unstable(excess - 1);
+        for &(_, _, owner, state) in &ranked[..excess] {
+            let key = PolicyKey { owner, state };
+            self.shard(&key).write().unwrap().remove(&key);
+        }
+        excess
+    }
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+}
+
+fn parse_binary(bytes: &[u8]) -> Result<Vec<PolicyRecord>, String> {
+    let header = BINARY_MAGIC.len() + 4 + 8;
+    if bytes.len() < header || &bytes[..4] != BINARY_MAGIC {
+        return Err("not a binary policy file".to_string());
+    }
+    let version = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+    if version != BINARY_VERSION {
+        return Err(format!("unsupported format version {}", version));
+    }
+    let count = u64::from_le_bytes(bytes[8..16].try_into().unwrap()) as usize;
+    let body = &bytes[header..];
+    if body.len() != count.saturating_mul(BINARY_RECORD_BYTES) {
+        return Err(format!("expected {} records, found {} bytes", count, body.len()));
+    }
+
+    let word = |record: &[u8], at: usize| -> [u8; 4] { record[at..at + 4].try_into().unwrap() };
+    Ok(body
+        .chunks_exact(BINARY_RECORD_BYTES)
+        .map(|record| PolicyRecord {
+            owner: u32::from_le_bytes(word(record, 0)),
+            state: u64::from_le_bytes(record[4..12].try_into().unwrap()),
+            policy: CompactPolicy {
+                q_values: std::array::from_fn(|i| f32::from_le_bytes(word(record, 12 + 4 * i))),
+                q_values_b: std::array::from_fn(|i| f32::from_le_bytes(word(record, 28 + 4 * i))),
+                visits: std::array::from_fn(|i| u32::from_le_bytes(word(record, 44 + 4 * i))),
+            },
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(state: u64) -> PolicyKey {
+        PolicyKey { owner: 0, state }
+    }
+
+    #[test]
+    fn test_trim_evicts_stale_or_unvisited_states() {
+        let mut table = PolicyTable::bounded(2, PolicyEviction::Lru, 0);
+        for state in 0..3 {
+            table.set_clock(state);
+            table.get_or_create(key(state));
+        }
+        table.set_clock(3);
+        table.get_or_create(key(0));
+        assert_eq!(table.take_inserts(), 3);
+        assert_eq!(table.trim(), 1);
+        assert_eq!(table.len(), 2);
+        // State 1 was the least recently used, so it comes back as a new insert
+        table.get_or_create(key(1));
+        assert_eq!(table.take_inserts(), 1);
+
+        let mut table = PolicyTable::bounded(1, PolicyEviction::LeastVisited, 0);
+        let mut visited = table.get_or_create(key(0));
+        visited.visits = [1, 0, 0, 0];
+        table.update(key(0), visited);
+        table.set_clock(1);
+        table.get_or_create(key(1));
+        assert_eq!(table.trim(), 1);
+        assert_eq!(table.get_or_create(key(0)).visits, [1, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_get_or_initial_does_not_insert() {
+        let table = PolicyTable::new(3);
+        let unseen = table.get_or_initial(key(7));
+        assert_eq!(table.len(), 0);
+        assert_eq!(table.take_inserts(), 0);
+        assert_eq!(table.get_or_create(key(7)), unseen);
+        assert_eq!(table.get_or_initial(key(7)), unseen);
+        assert_eq!(table.len(), 1);
+    }
+
+    #[test]
+    fn test_copy_owners() {
+        let mut table = PolicyTable::new(4);
+        let mut policy = table.get_or_create(key(1));
+        policy.visits = [2, 0, 0, 0];
+        table.update(key(1), policy);
+        table.get_or_create(PolicyKey { owner: 1, state: 5 });
+
+        // Owner 0's tables go to 7 and 8; owner 1 stays untouched
+        table.copy_owners(&[(7, 0), (8, 0)].into_iter().collect(), &FxHashSet::default());
+        assert_eq!(table.len(), 4);
+        for owner in [7, 8] {
+            assert_eq!(table.get_or_create(PolicyKey { owner, state: 1 }), policy);
+        }
+        assert_eq!(table.take_inserts(), 4);
+
+        // Owner 7 is copied to 9 as it goes; 8 and a copy of it to 10 go too
+        table.copy_owners(&[(9, 7), (10, 8)].into_iter().collect(), &[7, 8, 10].into_iter().collect());
+        assert_eq!(table.len(), 3);
+        assert_eq!(table.get_or_create(PolicyKey { owner: 9, state: 1 }), policy);
+        assert_eq!(table.take_inserts(), 1);
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let table = PolicyTable::new(1);
+        for state in 0..5 {
+            let mut policy = table.get_or_create(key(state));
+            policy.visits = [state as u32, 0, 1, 0];
+            table.update(key(state), policy);
+        }
+
+        let dir = std::env::temp_dir();
+        for name in ["ipd_policies_test.json", "ipd_policies_test.bin"] {
+            let path = dir.join(name);
+            table.save(&path).unwrap();
+            let mut loaded = PolicyTable::new(2);
+            assert_eq!(loaded.load(&path).unwrap(), 5);
+            assert_eq!(loaded.entries(), table.entries());
+            std::fs::remove_file(&path).unwrap();
+        }
+    }
+}
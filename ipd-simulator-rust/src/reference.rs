@@ -0,0 +1,184 @@
+use crate::agent::{Action, Agent};
+use crate::grid::{Grid, JointActionCounts, PolicyKey, SplitFitness};
+use crate::merge::{self, FitnessCombine, MergeRule};
+use crate::rng::{self, SimRng, DOMAIN_REFERENCE};
+use rand::Rng;
+use std::time::Instant;
+
+/// How the simulation advances one timestep
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum StepMode {
+    /// Synchronous 7-pass pipeline (`Grid::step`): every active cell plays
+    /// once per timestep, then merges and splits commit, migrants move and
+    /// any due evolutionary update runs
+    Parallel,
+    /// Sequential loop from algorithm.md: one game per timestep, like `singleGame()`
+    Reference,
+}
+
+/// Outcome of one reference step
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReferenceStep {
+    /// A game was played, or the chosen organism was cut off by vacant cells
+    Continue,
+    /// A single organism covers every occupied cell, so no game can be played
+    SingleOrganism,
+    /// No cell is occupied
+    Empty,
+}
+
+impl Grid {
+    /// Play one game exactly as `singleGame()`/`fight()` in grid-copy.js.
+    ///
+    /// A random active organism and a random neighboring organism play one
+    /// round, learn, and merge or split immediately. One call is one JS
+    /// timestep `n`, so an evolutionary update runs every `period` games.
+    /// An organism cut off by vacant cells just sits out the timestep.
+    pub fn step_reference(&mut self) -> ReferenceStep {
+        self.pass_stats.reset();
+        self.policy_table.set_clock(self.timestep);
+        let mut rng = rng::stream(self.seed, self.timestep, DOMAIN_REFERENCE, 0);
+        self.timestep += 1;
+
+        // Vacancies can leave no organism at all
+        let roots = self.active_roots();
+        if roots.is_empty() {
+            return ReferenceStep::Empty;
+        }
+        let my_idx = roots[rng.gen_range(0..roots.len())];
+
+        let opponents = self.organism_neighbors(my_idx);
+        if !opponents.is_empty() {
+            let opp_idx = opponents[rng.gen_range(0..opponents.len())];
+            self.fight(my_idx, opp_idx, &mut rng);
+            self.pass_stats.num_interactions = 1;
+        }
+
+        if let Some(evolution) = self.evolution.filter(|evolution| self.timestep.is_multiple_of(evolution.period)) {
+            let start = Instant::now();
+            self.pass_stats.replacements = self.evolve(evolution);
+            self.pass_stats.evolution_time = start.elapsed().as_micros();
+        }
+        self.record_arena_stats();
+        self.trim_policy_table();
+        if !opponents.is_empty() || roots.len() > 1 {
+            ReferenceStep::Continue
+        } else {
+            ReferenceStep::SingleOrganism
+        }
+    }
+
+    /// Roots of all cells bordering an organism, excluding the organism itself
+    fn organism_neighbors(&self, root: usize) -> Vec<usize> {
+        if let Some(index) = &self.neighborhoods {
+            return index
+                .neighbor_roots(root, &self.root_cache)
+                .into_iter()
+                .map(|r| r as usize)
+                .filter(|&r| self.is_occupied(r))
+                .collect();
+        }
+        
+        let mut neighbors = Vec::with_capacity(8);
+        let mut result = Vec::new();
+        for cell in self.member_cells(root) {
+            self.get_neighbors(cell, &mut neighbors);
+            for &n in &neighbors {
+                let opp_root = self.root_cache[n] as usize;
+                if opp_root != root && self.is_occupied(opp_root) && !result.contains(&opp_root) {
+                    result.push(opp_root);
+                }
+            }
+        }
+        result
+    }
+
+    /// One round of the game with immediate learning, merge and split
+    fn fight(&mut self, my_idx: usize, opp_idx: usize, rng: &mut SimRng) {
+        let my_agent = self.agents[my_idx].clone();
+        let opp_agent = self.agents[opp_idx].clone();
+
+        let my_key = self.policy_key(&my_agent, self.state_encoder.encode(&my_agent, &opp_agent));
+        let opp_key = self.policy_key(&opp_agent, self.state_encoder.encode(&opp_agent, &my_agent));
+
+        let my_action = self.choose_action(&my_agent, my_key, rng);
+        let opp_action = self.choose_action(&opp_agent, opp_key, rng);
+        let mut games = JointActionCounts::default();
+        games.record(my_action, opp_action);
+        self.record_games(games);
+
+        let merge_rule = MergeRule::reference();
+        let (me, opp) = ((my_idx as u32, my_agent.fitness), (opp_idx as u32, opp_agent.fitness));
+        let (inherit_from, _) = merge_rule.pick_parents(me, opp, rng);
+        let fitter = merge::fitter(me, opp);
+
+        // Update memories and scores
+        let my_payoff = self.payoff_table.get(my_action, opp_action);
+        let opp_payoff = self.payoff_table.get(opp_action, my_action);
+        for (idx, action, opp, payoff) in [
+            (my_idx, my_action, opp_action, my_payoff),
+            (opp_idx, opp_action, my_action, opp_payoff),
+        ] {
+            let agent = &mut self.agents[idx];
+            agent.add_to_memory(action, opp);
+            agent.fitness += payoff;
+            agent.last_action = action as u8;
+        }
+
+        // Learn from the next state, seen after both memories were updated.
+        // Updates are applied one after the other, so agents sharing a table
+        // see each other's changes just as they do in JS.
+        for (idx, opp, key, action, payoff) in [
+            (my_idx, opp_idx, my_key, my_action, my_payoff),
+            (opp_idx, my_idx, opp_key, opp_action, opp_payoff),
+        ] {
+            let agent = &self.agents[idx];
+            let opponent = &self.agents[opp];
+            if !agent.strategy.learns() || self.freeze_policies {
+                continue;
+            }
+            let next_key = self.policy_key(agent, self.state_encoder.encode(agent, opponent));
+            let next_policy = self.policy_table.get_or_create(next_key);
+            let policy = self.policy_table.get_or_create(key);
+            let updated = self.learning_rule.update(&policy, action, payoff, &next_policy, self.learning_params(agent), rng);
+            self.policy_table.update(key, updated);
+        }
+
+        // Physically change the board
+        if merge_rule.triggers(my_action, opp_action, rng) {
+            self.merge_now(my_idx, opp_idx, inherit_from as usize, fitter, merge_rule.fitness);
+        } else {
+            if my_action == Action::Split && self.agents[my_idx].is_multicellular() {
+                self.split_now(my_idx);
+            }
+            if opp_action == Action::Split && self.agents[opp_idx].is_multicellular() {
+                self.split_now(opp_idx);
+            }
+        }
+    }
+
+    /// Action from the policy of a learner, or from a fixed strategy
+    fn choose_action(&self, agent: &Agent, key: PolicyKey, rng: &mut SimRng) -> Action {
+        if agent.strategy.learns() {
+            self.learning_params(agent).selector.choose(&self.play_policy(key), rng)
+        } else {
+            agent.strategy.action(agent, rng)
+        }
+    }
+
+    /// Replace two organisms with a super-agent; `fitter` was fitter before the game
+    fn merge_now(&mut self, agent1: usize, agent2: usize, inherit_from: usize, fitter: u32, fitness: FitnessCombine) {
+        let mut new_agent = self.agents[inherit_from].clone();
+        new_agent.fitness = fitness.combine(self.agents[agent1].fitness, self.agents[agent2].fitness);
+        new_agent.parent_1 = agent1 as u32;
+        new_agent.parent_2 = agent2 as u32;
+        new_agent.generation += 1;
+
+        self.attach_merged(new_agent, fitter);
+    }
+
+    /// Dissolve a super-agent; both parents take over its fitness, memory and policy
+    fn split_now(&mut self, idx: usize) {
+        self.split_agent(idx, SplitFitness::Inherit);
+    }
+}
@@ -0,0 +1,57 @@
+use rand::SeedableRng;
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+/// Fast RNG used for every random draw in the simulation
+pub type SimRng = Xoshiro256PlusPlus;
+
+// Stream domains, so draws for different purposes never share a sequence
+pub const DOMAIN_INIT: u64 = 1;
+pub const DOMAIN_POLICY_INIT: u64 = 2;
+pub const DOMAIN_PAIRING: u64 = 3;
+pub const DOMAIN_GAME: u64 = 4;
+pub const DOMAIN_REFERENCE: u64 = 5;
+pub const DOMAIN_HYPERPARAMS: u64 = 6;
+pub const DOMAIN_MUTATION: u64 = 7;
+pub const DOMAIN_STRATEGY: u64 = 8;
+pub const DOMAIN_MEMORY: u64 = 9;
+pub const DOMAIN_POLICY_UPDATE: u64 = 10;
+pub const DOMAIN_GRAPH: u64 = 11;
+pub const DOMAIN_VACANCY: u64 = 12;
+pub const DOMAIN_MIGRATION: u64 = 13;
+pub const DOMAIN_EVOLUTION: u64 = 14;
+
+/// SplitMix64 finalizer
+#[inline]
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+/// Counter-based stream for one (timestep, domain, index) triple.
+///
+/// Each draw site derives its own generator from the run seed instead of
+/// using a thread-local RNG, so results do not depend on how rayon
+/// schedules work across threads.
+#[inline]
+pub fn stream(seed: u64, timestep: u64, domain: u64, index: u64) -> SimRng {
+    let key = mix(mix(mix(seed ^ domain.wrapping_mul(0x9e3779b97f4a7c15)) ^ timestep) ^ index);
+    SimRng::seed_from_u64(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn test_streams_are_reproducible_and_distinct() {
+        let a: u64 = stream(7, 3, DOMAIN_GAME, 11).gen();
+        let b: u64 = stream(7, 3, DOMAIN_GAME, 11).gen();
+        let c: u64 = stream(7, 3, DOMAIN_GAME, 12).gen();
+        let d: u64 = stream(7, 3, DOMAIN_PAIRING, 11).gen();
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_ne!(a, d);
+    }
+}
@@ -0,0 +1,117 @@
+use crate::agent::Agent;
+
+/// How an agent's view of a game becomes a policy table state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum StateEncoder {
+    /// Both full memories and both lengths (`Agent::get_memory_hash`)
+    Packed,
+    /// JS `stitchmem`: both players' own recent actions, cut to the shorter
+    /// memory and interleaved newest first
+    Stitch,
+    /// Only the agent's own memory of its games
+    Own,
+    /// Only the opponent's memory of its games
+    Opponent,
+}
+
+impl StateEncoder {
+    /// State key for `me` playing `opp`
+    #[inline]
+    pub fn encode(self, me: &Agent, opp: &Agent) -> u64 {
+        match self {
+            StateEncoder::Packed => me.get_memory_hash(opp.memory_bits, opp.mem_length),
+            StateEncoder::Stitch => {
+                // JS memlists hold only each agent's own actions, which are
+                // the high half of every pair in our memories
+                let len = me.mem_length.min(opp.mem_length) as u32;
+                let mut state = (len as u64) << 56;
+                for age in 0..len {
+                    let pair = ((me.remembered(age).0 as u32) << 2) | opp.remembered(age).0 as u32;
+                    state |= (pair as u64) << (age * 4);
+                }
+                state
+            }
+            StateEncoder::Own => ((me.mem_length as u64) << 56) | window(me) as u64,
+            StateEncoder::Opponent => ((opp.mem_length as u64) << 48) | window(opp) as u64,
+        }
+    }
+}
+
+impl StateEncoder {
+    /// The memory (`memory_bits`, `mem_length`) a fixed strategy would read
+    /// in this state, so learned actions can be compared with it.
+    ///
+    /// `Stitch` states pair both players' own past actions; `Opponent`
+    /// states are read from the opponent's side, its own moves taken as the
+    /// opponent's moves and its opponents' moves as the agent's.
+    pub fn decode(self, state: u64) -> (u32, u8) {
+        const WINDOW: u64 = (1 << 28) - 1;
+        match self {
+            StateEncoder::Packed => (((state >> 28) & WINDOW) as u32, (state >> 60) as u8),
+            StateEncoder::Own => ((state & WINDOW) as u32, (state >> 56) as u8),
+            StateEncoder::Stitch => {
+                let len = (state >> 56) as u32;
+                let bits = (0..len).map(|age| (((state >> (age * 4)) & 0xF) as u32) << ((len - 1 - age) * 4)).sum();
+                (bits, len as u8)
+            }
+            StateEncoder::Opponent => {
+                let len = ((state >> 48) & 0xFF) as u32;
+                let bits = (0..len)
+                    .map(|slot| {
+                        let pair = ((state >> (slot * 4)) & 0xF) as u32;
+                        (((pair & 0b11) << 2) | (pair >> 2)) << (slot * 4)
+                    })
+                    .sum();
+                (bits, len as u8)
+            }
+        }
+    }
+}
+
+/// Memory bits inside the agent's window
+#[inline]
+fn window(agent: &Agent) -> u32 {
+    agent.memory_bits & ((1u32 << (agent.mem_length as u32 * 4)) - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::Action;
+
+    #[test]
+    fn test_stitch_interleaves_own_actions() {
+        let mut rng = rand::thread_rng();
+        let mut me = Agent::new(0, &mut rng);
+        let mut opp = Agent::new(1, &mut rng);
+        me.mem_length = 3;
+        opp.mem_length = 2;
+        me.memory_bits = 0;
+        opp.memory_bits = 0;
+
+        // My own actions, oldest to newest: C, M, D
+        me.add_to_memory(Action::Cooperate, Action::Split);
+        me.add_to_memory(Action::Merge, Action::Split);
+        me.add_to_memory(Action::Defect, Action::Split);
+        // The opponent's own actions: S, C
+        opp.add_to_memory(Action::Split, Action::Defect);
+        opp.add_to_memory(Action::Cooperate, Action::Defect);
+
+        // JS "DCMS": newest first, cut to two pairs
+        let state = StateEncoder::Stitch.encode(&me, &opp);
+        assert_eq!(state >> 56, 2);
+        assert_eq!(state & 0xFF, (0b1011 << 4) | 0b0100);
+
+        // The other side sees the same actions with the roles swapped
+        assert_eq!(StateEncoder::Stitch.encode(&opp, &me) & 0xFF, (0b1110 << 4) | 0b0001);
+
+        // Decoding gives back the memory a fixed strategy reads
+        for encoder in [StateEncoder::Packed, StateEncoder::Own] {
+            assert_eq!(encoder.decode(encoder.encode(&me, &opp)), (me.memory_bits, 3));
+        }
+        assert_eq!(StateEncoder::Stitch.decode(state), (0b0100_1011, 2));
+
+        assert_ne!(StateEncoder::Own.encode(&me, &opp), StateEncoder::Opponent.encode(&me, &opp));
+        assert_eq!(StateEncoder::Own.encode(&me, &opp), StateEncoder::Own.encode(&me, &me));
+    }
+}
@@ -0,0 +1,179 @@
+use crate::agent::{Action, Agent};
+use clap::ValueEnum;
+use rand::Rng;
+use std::fmt;
+use std::str::FromStr;
+
+/// What decides an agent's moves
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+#[repr(u8)]
+pub enum Strategy {
+    /// Q-learner driven by the policy table
+    Learner,
+    /// Tit-for-tat: cooperate unless the opponent last defected or split
+    Tft,
+    /// Defect if the opponent defected or split anywhere in memory
+    Grim,
+    /// Pavlov / win-stay lose-shift: repeat after a nice opponent, switch otherwise
+    Wsls,
+    /// Always cooperate
+    AllC,
+    /// Always defect
+    AllD,
+    /// Cooperate or defect with equal probability
+    Random,
+    /// Tit-for-tat that offers Merge instead of cooperating
+    MergeHappy,
+}
+
+impl Strategy {
+    pub const ALL: [Strategy; 8] = [
+        Strategy::Learner,
+        Strategy::Tft,
+        Strategy::Grim,
+        Strategy::Wsls,
+        Strategy::AllC,
+        Strategy::AllD,
+        Strategy::Random,
+        Strategy::MergeHappy,
+    ];
+
+    /// Whether moves come from (and update) the policy table
+    #[inline]
+    pub fn learns(self) -> bool {
+        self == Strategy::Learner
+    }
+
+    /// Name used in CSV columns: the CLI name in snake case
+    pub fn name(self) -> String {
+        self.to_possible_value().unwrap().get_name().replace('-', "_")
+    }
+
+    /// Move of a fixed-strategy agent, read from its memory of past games.
+    ///
+    /// Unplayed memory slots hold Cooperate pairs, so reactive strategies
+    /// open nicely. Must not be called for learners.
+    pub fn action<R: Rng>(self, agent: &Agent, rng: &mut R) -> Action {
+        let nice = |action: Action| matches!(action, Action::Cooperate | Action::Merge);
+        let (my_last, opp_last) = agent.remembered(0);
+        match self {
+            Strategy::Learner => unreachable!("learners act through their policy"),
+            Strategy::Tft => if nice(opp_last) { Action::Cooperate } else { Action::Defect },
+            Strategy::Grim => {
+                let betrayed = (0..agent.mem_length as u32).any(|age| !nice(agent.remembered(age).1));
+                if betrayed { Action::Defect } else { Action::Cooperate }
+            }
+            Strategy::Wsls => {
+                let cooperated = nice(my_last);
+                if cooperated == nice(opp_last) { Action::Cooperate } else { Action::Defect }
+            }
+            Strategy::AllC => Action::Cooperate,
+            Strategy::AllD => Action::Defect,
+            Strategy::Random => if rng.gen::<bool>() { Action::Cooperate } else { Action::Defect },
+            Strategy::MergeHappy => if nice(opp_last) { Action::Merge } else { Action::Defect },
+        }
+    }
+}
+
+/// Initial share of each strategy, parsed from `NAME=WEIGHT,...`
+#[derive(Debug, Clone)]
+pub struct StrategyMix {
+    weights: Vec<(Strategy, f32)>,
+}
+
+impl Default for StrategyMix {
+    fn default() -> Self {
+        Self { weights: vec![(Strategy::Learner, 1.0)] }
+    }
+}
+
+impl StrategyMix {
+    /// Strategy at cumulative fraction `u` in [0, 1)
+    pub fn pick(&self, u: f32) -> Strategy {
+        let total: f32 = self.weights.iter().map(|&(_, w)| w).sum();
+        let mut target = u * total;
+        for &(strategy, weight) in &self.weights {
+            if target < weight {
+                return strategy;
+            }
+            target -= weight;
+        }
+        self.weights.last().unwrap().0
+    }
+}
+
+impl FromStr for StrategyMix {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let mut weights = Vec::new();
+        for entry in spec.split(',') {
+            let (name, weight) = entry
+                .split_once('=')
+                .ok_or_else(|| format!("expected NAME=WEIGHT, got \"{}\"", entry))?;
+            let strategy = Strategy::from_str(name.trim(), true)?;
+            let weight: f32 = weight.trim().parse().map_err(|_| format!("invalid weight \"{}\"", weight))?;
+            if !weight.is_finite() || weight < 0.0 {
+                return Err(format!("weight for {} must be a non-negative number", name));
+            }
+            weights.push((strategy, weight));
+        }
+        if weights.iter().map(|&(_, w)| w).sum::<f32>() <= 0.0 {
+            return Err("strategy weights sum to zero".to_string());
+        }
+        Ok(Self { weights })
+    }
+}
+
+impl fmt::Display for StrategyMix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let entries: Vec<String> = self.weights.iter().map(|(s, w)| format!("{}={}", s.to_possible_value().unwrap().get_name(), w)).collect();
+        write!(f, "{}", entries.join(","))
+    }
+}
+
+/// How the initial strategies are placed on the grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum StrategyLayout {
+    /// Every cell draws its strategy independently
+    Random,
+    /// Square patches of cells share one draw
+    Patches,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_reactive_strategies_read_memory() {
+        let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(0);
+        let mut agent = Agent::new(0, &mut rng);
+        agent.mem_length = 3;
+        agent.memory_bits = 0;
+        assert_eq!(Strategy::Tft.action(&agent, &mut rng), Action::Cooperate);
+        assert_eq!(Strategy::MergeHappy.action(&agent, &mut rng), Action::Merge);
+
+        agent.add_to_memory(Action::Cooperate, Action::Defect);
+        assert_eq!(Strategy::Tft.action(&agent, &mut rng), Action::Defect);
+        assert_eq!(Strategy::Wsls.action(&agent, &mut rng), Action::Defect);
+
+        // Grim remembers the defection while it is in memory, TFT forgives at once
+        agent.add_to_memory(Action::Defect, Action::Cooperate);
+        assert_eq!(Strategy::Tft.action(&agent, &mut rng), Action::Cooperate);
+        assert_eq!(Strategy::Grim.action(&agent, &mut rng), Action::Defect);
+        // WSLS: defecting against a cooperator paid off, so it stays
+        assert_eq!(Strategy::Wsls.action(&agent, &mut rng), Action::Defect);
+    }
+
+    #[test]
+    fn test_parse_mix() {
+        let mix: StrategyMix = "learner=3,all-d=1".parse().unwrap();
+        assert_eq!(mix.pick(0.0), Strategy::Learner);
+        assert_eq!(mix.pick(0.74), Strategy::Learner);
+        assert_eq!(mix.pick(0.76), Strategy::AllD);
+        assert!("learner=0".parse::<StrategyMix>().is_err());
+        assert!("saint=1".parse::<StrategyMix>().is_err());
+    }
+}
@@ -0,0 +1,164 @@
+use crate::graph::Graph;
+use std::sync::Arc;
+
+/// Cell layout and neighborhood shape of a lattice
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Lattice {
+    /// Square cells, neighbors within Chebyshev distance `radius` (JS `getNeighbors` at radius 1)
+    Moore,
+    /// Square cells, neighbors within Manhattan distance `radius`
+    VonNeumann,
+    /// Hexagonal cells in odd-row offset layout, neighbors within hex distance `radius`
+    Hex,
+}
+
+/// Which cells neighbor each other
+#[derive(Debug, Clone)]
+pub enum Topology {
+    /// Cells on the `width` x `height` grid
+    Lattice {
+        lattice: Lattice,
+        radius: usize,
+        /// Wrap around the edges onto a torus
+        wrap: bool,
+    },
+    /// Cells are the nodes of a graph, laid out as a single grid row
+    Graph(Arc<Graph>),
+}
+
+impl Default for Topology {
+    fn default() -> Self {
+        Topology::Lattice { lattice: Lattice::Moore, radius: 1, wrap: false }
+    }
+}
+
+impl Topology {
+    /// Check the topology fits a grid with `height` rows
+    pub fn validate(&self, height: usize) -> Result<(), String> {
+        match *self {
+            Topology::Lattice { radius: 0, .. } => Err("neighborhood radius must be at least 1".to_string()),
+            Topology::Lattice { lattice: Lattice::Hex, wrap: true, .. } if height % 2 == 1 => {
+                Err("a wrapped hex lattice needs an even grid height".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Neighbors of cell `idx`, excluding the cell itself: in row-major
+    /// order on lattices, by node id on graphs
+    pub fn neighbors(&self, idx: usize, width: usize, height: usize, out: &mut Vec<usize>) {
+        out.clear();
+        let (lattice, radius, wrap) = match self {
+            Topology::Lattice { lattice, radius, wrap } => (*lattice, *radius, *wrap),
+            Topology::Graph(graph) => {
+                out.extend(graph.neighbors(idx).iter().map(|&n| n as usize));
+                return;
+            }
+        };
+        let (w, h) = (width as i64, height as i64);
+        let (x, y) = ((idx % width) as i64, (idx / width) as i64);
+        let r = radius as i64;
+
+        let mut push = |nx: i64, ny: i64| {
+            let (nx, ny) = if wrap {
+                (nx.rem_euclid(w), ny.rem_euclid(h))
+            } else if (0..w).contains(&nx) && (0..h).contains(&ny) {
+                (nx, ny)
+            } else {
+                return;
+            };
+            let neighbor = (ny * w + nx) as usize;
+            // A wrapped radius can reach the same cell twice, or the cell itself
+            if neighbor != idx && !(wrap && out.contains(&neighbor)) {
+                out.push(neighbor);
+            }
+        };
+
+        match lattice {
+            Lattice::Moore | Lattice::VonNeumann => {
+                for dy in -r..=r {
+                    for dx in -r..=r {
+                        if (dx == 0 && dy == 0) || (lattice == Lattice::VonNeumann && dx.abs() + dy.abs() > r) {
+                            continue;
+                        }
+                        push(x + dx, y + dy);
+                    }
+                }
+            }
+            Lattice::Hex => {
+                // Odd rows sit half a cell to the right; step in axial coordinates
+                let q = x - (y - y.rem_euclid(2)) / 2;
+                for dr in -r..=r {
+                    let ny = y + dr;
+                    for dq in (-r).max(-dr - r)..=r.min(-dr + r) {
+                        if dq == 0 && dr == 0 {
+                            continue;
+                        }
+                        push(q + dq + (ny - ny.rem_euclid(2)) / 2, ny);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Whether frames can draw cells at their grid position
+    pub fn is_lattice(&self) -> bool {
+        matches!(self, Topology::Lattice { .. })
+    }
+
+    /// Whether odd grid rows are drawn half a cell to the right
+    pub fn is_hex(&self) -> bool {
+        matches!(self, Topology::Lattice { lattice: Lattice::Hex, .. })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lattice(lattice: Lattice, radius: usize, wrap: bool) -> Topology {
+        Topology::Lattice { lattice, radius, wrap }
+    }
+
+    fn count(topology: &Topology, idx: usize, width: usize, height: usize) -> usize {
+        let mut out = Vec::new();
+        topology.neighbors(idx, width, height, &mut out);
+        out.len()
+    }
+
+    #[test]
+    fn test_neighborhood_sizes() {
+        let moore = Topology::default();
+        assert_eq!((count(&moore, 12, 5, 5), count(&moore, 0, 5, 5)), (8, 3));
+        assert_eq!(count(&lattice(Lattice::Moore, 1, true), 0, 5, 5), 8);
+        assert_eq!(count(&lattice(Lattice::Moore, 2, false), 12, 5, 5), 24);
+        // Radius 2 on a 3x3 torus reaches every other cell once
+        assert_eq!(count(&lattice(Lattice::Moore, 2, true), 0, 3, 3), 8);
+
+        assert_eq!(count(&lattice(Lattice::VonNeumann, 1, false), 12, 5, 5), 4);
+        assert_eq!(count(&lattice(Lattice::VonNeumann, 2, false), 12, 5, 5), 12);
+
+        assert_eq!(count(&lattice(Lattice::Hex, 1, false), 12, 5, 5), 6);
+        assert_eq!(count(&lattice(Lattice::Hex, 2, false), 12, 5, 5), 18);
+
+        let graph = Topology::Graph(Arc::new(Graph::from_edges(3, &[(0, 1), (1, 2)])));
+        assert_eq!(count(&graph, 1, 3, 1), 2);
+    }
+
+    #[test]
+    fn test_wrapped_hex_is_symmetric() {
+        let hex = lattice(Lattice::Hex, 1, true);
+        let (width, height) = (6, 4);
+        let mut out = Vec::new();
+        let mut back = Vec::new();
+        for cell in 0..width * height {
+            hex.neighbors(cell, width, height, &mut out);
+            assert_eq!(out.len(), 6);
+            for &n in &out {
+                hex.neighbors(n, width, height, &mut back);
+                assert!(back.contains(&cell));
+            }
+        }
+        assert!(hex.validate(5).is_err());
+    }
+}
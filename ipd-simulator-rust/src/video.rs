@@ -1,11 +1,13 @@
 use crate::grid::{Grid, Statistics};
 use std::path::{Path, PathBuf};
 use std::fs;
-use std::sync::Arc;
-use std::thread;
 
 #[cfg(feature = "video")]
 use crossbeam::channel::{bounded, Sender, Receiver};
+#[cfg(feature = "video")]
+use std::sync::Arc;
+#[cfg(feature = "video")]
+use std::thread;
 
 #[cfg(feature = "video")]
 struct FrameData {
@@ -15,6 +17,7 @@ struct FrameData {
     data: Arc<Vec<u8>>,
 }
 
+#[cfg_attr(not(feature = "video"), allow(dead_code))]
 pub struct VideoEncoder {
     width: u32,
     height: u32,
@@ -27,6 +30,7 @@ pub struct VideoEncoder {
     encoder_threads: Vec<thread::JoinHandle<()>>,
 }
 
+#[cfg_attr(not(feature = "video"), allow(dead_code))]
 impl VideoEncoder {
     pub fn new(output_path: &Path, width: u32, height: u32, fps: u32) -> Result<Self, Box<dyn std::error::Error>> {
         // Create frames directory
@@ -201,22 +205,20 @@ impl VideoEncoder {
         let scale_x = self.width as f32 / grid.grid_width as f32;
         let scale_y = self.height as f32 / grid.grid_height as f32;
         
-        // Render grid
+        // Render grid; hex lattices shift odd rows by half a cell
+        let hex = grid.topology.is_hex();
         for y in 0..grid.grid_height {
+            let shift = if hex && y % 2 == 1 { 0.5 } else { 0.0 };
             for x in 0..grid.grid_width {
                 let idx = y * grid.grid_width + x;
-                let agent_idx = grid.find_root(idx);
-                
-                if agent_idx >= grid.agents.len() {
-                    continue;
+                if !grid.active_mask[idx] {
+                    continue; // Vacant cells stay black
                 }
-                
-                let agent = &grid.agents[agent_idx];
-                let color = self.get_agent_color(agent.get_organism_size());
+                let color = self.get_agent_color(grid.organism_size(idx));
                 
                 // Calculate pixel coordinates
-                let px_start = (x as f32 * scale_x) as u32;
-                let px_end = ((x + 1) as f32 * scale_x) as u32;
+                let px_start = ((x as f32 + shift) * scale_x) as u32;
+                let px_end = ((x as f32 + 1.0 + shift) * scale_x) as u32;
                 let py_start = (y as f32 * scale_y) as u32;
                 let py_end = ((y + 1) as f32 * scale_y) as u32;
                 
@@ -286,7 +288,7 @@ impl VideoEncoder {
         // Draw fitness indicator (blue block)
         let fitness_color = (0, 100, 255);
         let avg_fitness = if stats.total_agents > 0 {
-            stats.total_fitness as f64 / stats.total_agents as f64
+            stats.total_fitness / stats.total_agents as f64
         } else {
             0.0
         };
@@ -316,6 +318,7 @@ impl VideoEncoder {
         }
     }
     
+    #[cfg_attr(not(feature = "video"), allow(unused_mut))]
     pub fn finish(mut self) -> Result<(), Box<dyn std::error::Error>> {
         #[cfg(feature = "video")]
         {